@@ -0,0 +1,336 @@
+use std::collections::HashMap;
+
+use crate::{gc2d::Gc2dResult, graphics::{DrawParams, Graphics}, image::{ImageHandle, Quad}, sprite_sheet::{SpriteSheet, TagDirection}};
+
+// Duration used for frames of an atlas without durations
+const DEFAULT_FRAME_DURATION: f32 = 0.1;
+// Shortest frame (1 ms), shorter durations are clamped to it
+const MIN_FRAME_DURATION: f32 = 0.001;
+
+/*================================================================
+ *                        A N I M A T I O N
+ *================================================================*/
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PlayMode {
+    Loop,
+    Once,
+    PingPong,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct AnimationFrame {
+    pub quad: Quad,
+    // Duration in seconds
+    pub duration: f32,
+}
+
+type FrameCallback = Box<dyn FnMut(usize)>;
+
+pub struct Animation {
+    image: ImageHandle,
+    frames: Vec<AnimationFrame>,
+    mode: PlayMode,
+    speed: f32,
+
+    // State
+    current: usize,
+    timer: f32,
+    forward: bool,
+    paused: bool,
+    finished: bool,
+
+    // Events
+    frame_events: HashMap<usize, Vec<FrameCallback>>,
+    finish_events: Vec<FrameCallback>,
+}
+
+impl Animation {
+    /*
+     * new()
+     *
+     * @Brief : Create an animation without frames
+     */
+    pub fn new(image: ImageHandle, mode: PlayMode) -> Self {
+        Self {
+            image,
+            frames: Vec::new(),
+            mode,
+            speed: 1.,
+            current: 0,
+            timer: 0.,
+            forward: true,
+            paused: false,
+            finished: false,
+            frame_events: HashMap::new(),
+            finish_events: Vec::new(),
+        }
+    }
+
+    /*
+     * from_sheet()
+     *
+     * @Brief : Animation with the frames first..=last of a sprite sheet,
+     *          each frame lasting duration seconds
+     */
+    pub fn from_sheet(sheet: &SpriteSheet, first: usize, last: usize, duration: f32, mode: PlayMode) -> Gc2dResult<Self> {
+        let mut animation = Animation::new(sheet.image, mode);
+        for index in first..=last {
+            let quad = sheet
+                .get_quad_at(index)
+                .ok_or_else(|| format!("Frame {} is not in the sprite sheet", index))?;
+            animation.add_frame(quad, duration);
+        }
+        Ok(animation)
+    }
+
+    /*
+     * from_aseprite_tag()
+     *
+     * @Brief : Animation of an Aseprite tag, with the durations of the atlas
+     *          The tag direction gives the play mode and the frames order
+     */
+    pub fn from_aseprite_tag(sheet: &SpriteSheet, tag: &str) -> Gc2dResult<Self> {
+        let tag = sheet
+            .get_tag(tag)
+            .ok_or_else(|| format!("Tag \"{}\" is not in the sprite sheet", tag))?;
+
+        let mode = match tag.direction {
+            TagDirection::Forward | TagDirection::Reverse => PlayMode::Loop,
+            TagDirection::PingPong | TagDirection::PingPongReverse => PlayMode::PingPong,
+        };
+
+        let mut indexes: Vec<usize> = (tag.from..=tag.to).collect();
+        if tag.direction == TagDirection::Reverse || tag.direction == TagDirection::PingPongReverse {
+            indexes.reverse();
+        }
+
+        let mut animation = Animation::new(sheet.image, mode);
+        for index in indexes {
+            let frame = &sheet.get_frames()[index];
+            animation.add_frame(frame.quad, frame.duration.unwrap_or(DEFAULT_FRAME_DURATION));
+        }
+        Ok(animation)
+    }
+
+    /*
+     * add_frame()
+     *
+     * @Brief : Add a frame lasting duration seconds (at least 1 ms)
+     */
+    pub fn add_frame(&mut self, quad: Quad, duration: f32) {
+        self.frames.push(AnimationFrame { quad, duration: duration.max(MIN_FRAME_DURATION) });
+    }
+
+    pub fn get_frames(&self) -> &[AnimationFrame] {
+        &self.frames
+    }
+
+    //=======================================================================
+    //                             PLAYBACK
+    //=======================================================================
+    /*
+     * update()
+     *
+     * @Brief : Advance the animation of dt seconds
+     */
+    pub fn update(&mut self, dt: f32) {
+        if self.paused || self.finished || self.frames.is_empty() {
+            return;
+        }
+
+        self.timer += dt * self.speed;
+
+        // Whole cycles end on the frame they started from : skip them
+        // (ex : after a long pause), without calling their frame events
+        if let Some(cycle) = self.cycle_duration() {
+            if self.timer >= cycle {
+                self.timer %= cycle;
+            }
+        }
+
+        while self.timer >= self.frames[self.current].duration {
+            self.timer -= self.frames[self.current].duration;
+
+            if !self.next_frame() {
+                self.timer = 0.;
+                self.finished = true;
+                let current = self.current;
+                for callback in self.finish_events.iter_mut() {
+                    callback(current);
+                }
+                break;
+            }
+
+            let current = self.current;
+            if let Some(callbacks) = self.frame_events.get_mut(&current) {
+                for callback in callbacks.iter_mut() {
+                    callback(current);
+                }
+            }
+        }
+    }
+
+    // Time to play all the frames and come back to the same frame and
+    // direction, None for an animation played once
+    fn cycle_duration(&self) -> Option<f32> {
+        let total: f32 = self.frames.iter().map(|frame| frame.duration).sum();
+        match self.mode {
+            PlayMode::Loop => Some(total),
+            PlayMode::Once => None,
+            // The first and last frames are played once per cycle, the others twice
+            PlayMode::PingPong if self.frames.len() > 1 => {
+                Some(2. * total - self.frames[0].duration - self.frames[self.frames.len() - 1].duration)
+            },
+            PlayMode::PingPong => Some(total),
+        }
+    }
+
+    // Go to the next frame, false when the animation is over
+    fn next_frame(&mut self) -> bool {
+        let count = self.frames.len();
+        match self.mode {
+            PlayMode::Loop => {
+                self.current = (self.current + 1) % count;
+                true
+            },
+            PlayMode::Once => {
+                if self.current + 1 < count {
+                    self.current += 1;
+                    true
+                } else {
+                    false
+                }
+            },
+            PlayMode::PingPong => {
+                if count > 1 {
+                    if self.forward && self.current + 1 == count {
+                        self.forward = false;
+                    } else if !self.forward && self.current == 0 {
+                        self.forward = true;
+                    }
+                    self.current = if self.forward { self.current + 1 } else { self.current - 1 };
+                }
+                true
+            },
+        }
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /*
+     * is_finished()
+     *
+     * @Brief : True when an animation played Once reached its last frame
+     */
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /*
+     * reset()
+     *
+     * @Brief : Go back to the first frame and play again
+     */
+    pub fn reset(&mut self) {
+        self.current = 0;
+        self.timer = 0.;
+        self.forward = true;
+        self.finished = false;
+    }
+
+    pub fn set_mode(&mut self, mode: PlayMode) {
+        self.mode = mode;
+    }
+
+    pub fn get_mode(&self) -> PlayMode {
+        self.mode
+    }
+
+    /*
+     * set_speed()
+     *
+     * @Brief : Speed multiplier (1. = durations of the frames)
+     */
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.max(0.);
+    }
+
+    pub fn get_speed(&self) -> f32 {
+        self.speed
+    }
+
+    /*
+     * set_frame()
+     *
+     * @Brief : Jump to a frame (ignored if out of the frames)
+     */
+    pub fn set_frame(&mut self, index: usize) {
+        if index < self.frames.len() {
+            self.current = index;
+            self.timer = 0.;
+        }
+    }
+
+    pub fn get_frame(&self) -> usize {
+        self.current
+    }
+
+    /*
+     * get_quad()
+     *
+     * @Brief : Quad of the current frame
+     */
+    pub fn get_quad(&self) -> Option<Quad> {
+        self.frames.get(self.current).map(|frame| frame.quad)
+    }
+
+    //=======================================================================
+    //                             EVENTS
+    //=======================================================================
+    /*
+     * on_frame()
+     *
+     * @Brief : Call callback each time the animation enters the frame index
+     */
+    pub fn on_frame<F: FnMut(usize) + 'static>(&mut self, index: usize, callback: F) {
+        self.frame_events.entry(index).or_default().push(Box::new(callback));
+    }
+
+    /*
+     * on_finish()
+     *
+     * @Brief : Call callback when an animation played Once is over
+     */
+    pub fn on_finish<F: FnMut(usize) + 'static>(&mut self, callback: F) {
+        self.finish_events.push(Box::new(callback));
+    }
+
+    //=======================================================================
+    //                             DRAW
+    //=======================================================================
+    pub fn draw(&self, graphics: &mut Graphics, x: f32, y: f32, angle: f64) -> Gc2dResult<()> {
+        self.draw_with(graphics, &DrawParams::new(x, y).with_rotation_degrees(angle as f32))
+    }
+
+    /*
+     * draw_with()
+     *
+     * @Brief : Draw the current frame with scale, origin, flip... (DrawParams)
+     */
+    pub fn draw_with(&self, graphics: &mut Graphics, params: &DrawParams) -> Gc2dResult<()> {
+        match self.get_quad() {
+            Some(quad) => graphics.draw_with(self.image, Some(quad), params),
+            None => Err(String::from("Animation has no frames")),
+        }
+    }
+}
@@ -0,0 +1,360 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::{color::Color, gc2d::Gc2dResult, image_data::ImageData};
+
+/*================================================================
+ *                     B I T M A P   F O N T
+ *================================================================*/
+/*
+ * Fonts drawn from images :
+ *
+ *   AngelCode BMFont : a .fnt file (text or XML) describing the glyphs,
+ *                      their offsets and the kerning, and its page images
+ *   Image font       : one image with the glyphs side by side, separated by
+ *                      columns of the color of the top left pixel (LÖVE style)
+ */
+#[derive(Clone, Copy, Debug, Default)]
+struct Glyph {
+    page: usize,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    x_offset: i32,
+    y_offset: i32,
+    x_advance: i32,
+}
+
+pub(crate) struct BitmapFont {
+    pages: Vec<ImageData>,
+    glyphs: HashMap<char, Glyph>,
+    kerning: HashMap<(char, char), i32>,
+    line_height: u32,
+    // Distance from the top of a line to the baseline
+    base: u32,
+}
+
+impl BitmapFont {
+    //=======================================================================
+    //                             BMFONT
+    //=======================================================================
+    /*
+     * from_bmfont()
+     *
+     * @Brief : Load a BMFont .fnt file (text or XML format) and its pages
+     */
+    pub fn from_bmfont(filename: &str) -> Gc2dResult<Self> {
+        let content = fs::read_to_string(filename)
+            .map_err(|e| format!("{}: {}", filename, e))?;
+        let directory = Path::new(filename).parent().unwrap_or(Path::new(""));
+
+        let description = if content.trim_start().starts_with('<') {
+            bmfont_xml(&content)
+        } else {
+            bmfont_text(&content)
+        }
+        .map_err(|e| format!("{}: {}", filename, e))?;
+
+        let mut pages = vec![ImageData::new(0, 0); description.pages.len()];
+        for (id, file) in description.pages {
+            let page = pages
+                .get_mut(id)
+                .ok_or_else(|| format!("{}: page {} is out of the page count", filename, id))?;
+            *page = ImageData::from_file(&directory.join(&file).to_string_lossy())?;
+        }
+
+        Ok(Self {
+            pages,
+            glyphs: description.glyphs,
+            kerning: description.kerning,
+            line_height: description.line_height,
+            base: description.base,
+        })
+    }
+
+    //=======================================================================
+    //                             IMAGE FONT
+    //=======================================================================
+    /*
+     * from_image()
+     *
+     * @Brief : Load an image font, glyphs are the characters of the image in order
+     */
+    pub fn from_image(filename: &str, glyphs: &str) -> Gc2dResult<Self> {
+        let image = ImageData::from_file(filename)?;
+        let separator = image
+            .get_pixel(0, 0)
+            .ok_or_else(|| format!("{}: the image is empty", filename))?;
+
+        let is_separator = |x: u32| image.get_pixel(x, 0) == Some(separator);
+
+        let mut font = Self {
+            pages: Vec::new(),
+            glyphs: HashMap::new(),
+            kerning: HashMap::new(),
+            line_height: image.get_height(),
+            base: image.get_height(),
+        };
+
+        let mut x = 0;
+        for c in glyphs.chars() {
+            while x < image.get_width() && is_separator(x) {
+                x += 1;
+            }
+            let start = x;
+            while x < image.get_width() && !is_separator(x) {
+                x += 1;
+            }
+            if start == x {
+                return Err(format!("{}: {} glyphs expected, the image has less", filename, glyphs.chars().count()));
+            }
+
+            font.glyphs.insert(c, Glyph {
+                page: 0,
+                x: start,
+                y: 0,
+                width: x - start,
+                height: image.get_height(),
+                x_offset: 0,
+                y_offset: 0,
+                x_advance: (x - start) as i32,
+            });
+        }
+
+        font.pages.push(image);
+        Ok(font)
+    }
+
+    //=======================================================================
+    //                             TEXT
+    //=======================================================================
+    pub fn get_line_height(&self) -> u32 {
+        self.line_height
+    }
+
+    pub fn get_base(&self) -> u32 {
+        self.base
+    }
+
+    // Glyph of a character, a space for the characters not in the font
+    fn glyph(&self, c: char) -> Glyph {
+        match self.glyphs.get(&c) {
+            Some(glyph) => *glyph,
+            None => Glyph {
+                x_advance: self.glyphs.get(&' ').map_or(self.line_height as i32 / 2, |g| g.x_advance),
+                ..Glyph::default()
+            },
+        }
+    }
+
+    // Position of each glyph from the left of the text
+    fn layout(&self, text: &str) -> (Vec<(i32, Glyph)>, u32) {
+        let mut placed = Vec::new();
+        let mut x = 0;
+        let mut right = 0;
+        let mut previous = None;
+
+        for c in text.chars() {
+            if let Some(previous) = previous {
+                x += self.kerning.get(&(previous, c)).copied().unwrap_or(0);
+            }
+            let glyph = self.glyph(c);
+            placed.push((x, glyph));
+            right = right.max(x + glyph.x_offset + glyph.width as i32);
+            x += glyph.x_advance;
+            previous = Some(c);
+        }
+
+        (placed, right.max(x).max(0) as u32)
+    }
+
+    /*
+     * size_of()
+     *
+     * @Brief : (width, height) of a text in pixels
+     */
+    pub fn size_of(&self, text: &str) -> (u32, u32) {
+        (self.layout(text).1, self.line_height)
+    }
+
+    /*
+     * render()
+     *
+     * @Brief : Pixels of a text, the glyphs multiplied by color
+     */
+    pub fn render(&self, text: &str, color: Color) -> ImageData {
+        let (placed, width) = self.layout(text);
+        let mut data = ImageData::new(width.max(1), self.line_height.max(1));
+
+        for (x, glyph) in placed {
+            let page = match self.pages.get(glyph.page) {
+                Some(page) => page,
+                None => continue,
+            };
+
+            for gy in 0..glyph.height {
+                for gx in 0..glyph.width {
+                    let dx = x + glyph.x_offset + gx as i32;
+                    let dy = glyph.y_offset + gy as i32;
+                    if dx < 0 || dy < 0 {
+                        continue;
+                    }
+                    let (dx, dy) = (dx as u32, dy as u32);
+
+                    let (src, dst) = match (page.get_pixel(glyph.x + gx, glyph.y + gy), data.get_pixel(dx, dy)) {
+                        (Some(src), Some(dst)) => (src, dst),
+                        _ => continue,
+                    };
+                    let tinted = Color::rgba(
+                        (src.r as u16 * color.r as u16 / 255) as u8,
+                        (src.g as u16 * color.g as u16 / 255) as u8,
+                        (src.b as u16 * color.b as u16 / 255) as u8,
+                        (src.a as u16 * color.a as u16 / 255) as u8,
+                    );
+                    data.set_pixel(dx, dy, tinted.over(dst));
+                }
+            }
+        }
+        data
+    }
+}
+
+//=======================================================================
+//                             PARSING
+//=======================================================================
+#[derive(Default)]
+struct BmFontDescription {
+    line_height: u32,
+    base: u32,
+    pages: Vec<(usize, String)>,
+    glyphs: HashMap<char, Glyph>,
+    kerning: HashMap<(char, char), i32>,
+}
+
+impl BmFontDescription {
+    fn add_char<F: Fn(&str) -> Option<String>>(&mut self, get: F) -> Gc2dResult<()> {
+        let int = |key: &str| -> Gc2dResult<i32> {
+            get(key)
+                .unwrap_or_else(|| String::from("0"))
+                .parse::<i32>()
+                .map_err(|_| format!("char: invalid {}", key))
+        };
+
+        let id = int("id")?;
+        let c = match char::from_u32(id as u32) {
+            Some(c) => c,
+            // Ex : id=-1, the glyph of the characters missing
+            None => return Ok(()),
+        };
+
+        self.glyphs.insert(c, Glyph {
+            page: int("page")?.max(0) as usize,
+            x: int("x")?.max(0) as u32,
+            y: int("y")?.max(0) as u32,
+            width: int("width")?.max(0) as u32,
+            height: int("height")?.max(0) as u32,
+            x_offset: int("xoffset")?,
+            y_offset: int("yoffset")?,
+            x_advance: int("xadvance")?,
+        });
+        Ok(())
+    }
+
+    fn add_kerning<F: Fn(&str) -> Option<String>>(&mut self, get: F) {
+        let int = |key: &str| get(key).and_then(|v| v.parse::<i64>().ok());
+        let (first, second, amount) = match (int("first"), int("second"), int("amount")) {
+            (Some(first), Some(second), Some(amount)) => (first, second, amount),
+            _ => return,
+        };
+        if let (Some(first), Some(second)) = (char::from_u32(first as u32), char::from_u32(second as u32)) {
+            self.kerning.insert((first, second), amount as i32);
+        }
+    }
+}
+
+// Text format : "tag key=value key="quoted value" ..." per line
+fn bmfont_text(content: &str) -> Gc2dResult<BmFontDescription> {
+    let mut description = BmFontDescription::default();
+
+    for line in content.lines() {
+        let (tag, attributes) = match line.trim().split_once(char::is_whitespace) {
+            Some((tag, rest)) => (tag, parse_attributes(rest)),
+            None => continue,
+        };
+        let get = |key: &str| attributes.get(key).cloned();
+
+        match tag {
+            "common" => {
+                description.line_height = get("lineHeight")
+                    .and_then(|v| v.parse().ok())
+                    .ok_or_else(|| String::from("common: invalid lineHeight"))?;
+                description.base = get("base").and_then(|v| v.parse().ok()).unwrap_or(description.line_height);
+            },
+            "page" => {
+                let id = get("id").and_then(|v| v.parse().ok()).ok_or_else(|| String::from("page: invalid id"))?;
+                let file = get("file").ok_or_else(|| String::from("page: missing file"))?;
+                description.pages.push((id, file));
+            },
+            "char" => description.add_char(get)?,
+            "kerning" => description.add_kerning(get),
+            _ => {},
+        }
+    }
+
+    if description.pages.is_empty() {
+        return Err(String::from("no page"));
+    }
+    Ok(description)
+}
+
+fn parse_attributes(text: &str) -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+    let mut rest = text.trim_start();
+
+    while let Some((key, value)) = rest.split_once('=') {
+        let key = key.trim().to_string();
+        let value = value.trim_start();
+        let (value, next) = match value.strip_prefix('"') {
+            Some(quoted) => match quoted.split_once('"') {
+                Some((value, next)) => (value, next),
+                None => (quoted, ""),
+            },
+            None => value.split_once(char::is_whitespace).unwrap_or((value, "")),
+        };
+        attributes.insert(key, value.to_string());
+        rest = next.trim_start();
+    }
+    attributes
+}
+
+// XML format : <font><common/><pages><page/></pages><chars><char/></chars><kernings><kerning/></kernings></font>
+fn bmfont_xml(content: &str) -> Gc2dResult<BmFontDescription> {
+    let document = roxmltree::Document::parse(content).map_err(|e| e.to_string())?;
+    let mut description = BmFontDescription::default();
+
+    for node in document.descendants().filter(|n| n.is_element()) {
+        let get = |key: &str| node.attribute(key).map(|v| v.to_string());
+
+        match node.tag_name().name() {
+            "common" => {
+                description.line_height = get("lineHeight")
+                    .and_then(|v| v.parse().ok())
+                    .ok_or_else(|| String::from("common: invalid lineHeight"))?;
+                description.base = get("base").and_then(|v| v.parse().ok()).unwrap_or(description.line_height);
+            },
+            "page" => {
+                let id = get("id").and_then(|v| v.parse().ok()).ok_or_else(|| String::from("page: invalid id"))?;
+                let file = get("file").ok_or_else(|| String::from("page: missing file"))?;
+                description.pages.push((id, file));
+            },
+            "char" => description.add_char(get)?,
+            "kerning" => description.add_kerning(get),
+            _ => {},
+        }
+    }
+
+    if description.pages.is_empty() {
+        return Err(String::from("no page"));
+    }
+    Ok(description)
+}
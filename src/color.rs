@@ -1,7 +1,10 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
+use crate::gc2d::Gc2dResult;
 
-
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -9,7 +12,6 @@ pub struct Color {
     pub a: u8,
 }
 
-#[allow(dead_code)]
 impl Color {
 
     pub(crate) fn to_sdl_color(&self) -> sdl2::pixels::Color {
@@ -21,6 +23,217 @@ impl Color {
         }
     }
 
+    //=======================================================================
+    //                             CONSTRUCTORS
+    //=======================================================================
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b, a: 255 }
+    }
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Color {
+        Color { r, g, b, a }
+    }
+
+    /*
+     * rgb_f32()
+     *
+     * @Brief : Color of components from 0. to 1.
+     */
+    pub fn rgb_f32(r: f32, g: f32, b: f32) -> Color {
+        Color::rgba_f32(r, g, b, 1.)
+    }
+
+    pub fn rgba_f32(r: f32, g: f32, b: f32, a: f32) -> Color {
+        Color {
+            r: to_u8(r),
+            g: to_u8(g),
+            b: to_u8(b),
+            a: to_u8(a),
+        }
+    }
+
+    /*
+     * to_f32()
+     *
+     * @Brief : Components from 0. to 1. (r, g, b, a)
+     */
+    pub fn to_f32(&self) -> (f32, f32, f32, f32) {
+        (self.r as f32 / 255., self.g as f32 / 255., self.b as f32 / 255., self.a as f32 / 255.)
+    }
+
+    /*
+     * from_hex()
+     *
+     * @Brief : Parse "#rgb", "#rgba", "#rrggbb" or "#rrggbbaa" (the # is optional)
+     */
+    pub fn from_hex(hex: &str) -> Gc2dResult<Color> {
+        let digits = hex.trim().trim_start_matches('#');
+        let error = || format!("\"{}\" is not a hex color", hex);
+
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(error());
+        }
+
+        let value = |i: usize, len: usize| {
+            let v = u8::from_str_radix(&digits[i * len..(i + 1) * len], 16).unwrap_or(0);
+            // "f" means "ff"
+            if len == 1 { v * 17 } else { v }
+        };
+
+        match digits.len() {
+            3 => Ok(Color::rgb(value(0, 1), value(1, 1), value(2, 1))),
+            4 => Ok(Color::rgba(value(0, 1), value(1, 1), value(2, 1), value(3, 1))),
+            6 => Ok(Color::rgb(value(0, 2), value(1, 2), value(2, 2))),
+            8 => Ok(Color::rgba(value(0, 2), value(1, 2), value(2, 2), value(3, 2))),
+            _ => Err(error()),
+        }
+    }
+
+    /*
+     * to_hex()
+     *
+     * @Brief : "#rrggbbaa"
+     */
+    pub fn to_hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}{:02x}", self.r, self.g, self.b, self.a)
+    }
+
+    /*
+     * from_name()
+     *
+     * @Brief : CSS named color, ex : "cornflowerblue" (case, spaces and _ are ignored)
+     */
+    pub fn from_name(name: &str) -> Option<Color> {
+        let name: String = name
+            .chars()
+            .filter(|c| *c != ' ' && *c != '_' && *c != '-')
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        NAMED_COLORS
+            .binary_search_by(|(n, _)| (*n).cmp(name.as_str()))
+            .ok()
+            .map(|i| NAMED_COLORS[i].1)
+    }
+
+    //=======================================================================
+    //                             HSV / HSL
+    //=======================================================================
+    /*
+     * from_hsv()
+     *
+     * @Brief : Hue in degrees, saturation and value from 0. to 1.
+     */
+    pub fn from_hsv(h: f32, s: f32, v: f32) -> Color {
+        let (s, v) = (s.clamp(0., 1.), v.clamp(0., 1.));
+        let c = v * s;
+        let (r, g, b) = hue_to_rgb(h, c);
+        let m = v - c;
+        Color::rgb_f32(r + m, g + m, b + m)
+    }
+
+    /*
+     * to_hsv()
+     *
+     * @Brief : (hue in degrees, saturation, value)
+     */
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let (r, g, b, _) = self.to_f32();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let s = if max > 0. { (max - min) / max } else { 0. };
+        (hue(r, g, b, max, min), s, max)
+    }
+
+    /*
+     * from_hsl()
+     *
+     * @Brief : Hue in degrees, saturation and lightness from 0. to 1.
+     */
+    pub fn from_hsl(h: f32, s: f32, l: f32) -> Color {
+        let (s, l) = (s.clamp(0., 1.), l.clamp(0., 1.));
+        let c = (1. - (2. * l - 1.).abs()) * s;
+        let (r, g, b) = hue_to_rgb(h, c);
+        let m = l - c / 2.;
+        Color::rgb_f32(r + m, g + m, b + m)
+    }
+
+    /*
+     * to_hsl()
+     *
+     * @Brief : (hue in degrees, saturation, lightness)
+     */
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let (r, g, b, _) = self.to_f32();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let l = (max + min) / 2.;
+        let s = if max > min { (max - min) / (1. - (2. * l - 1.).abs()) } else { 0. };
+        (hue(r, g, b, max, min), s, l)
+    }
+
+    //=======================================================================
+    //                             OPERATIONS
+    //=======================================================================
+    /*
+     * lerp()
+     *
+     * @Brief : Color between self (t = 0.) and other (t = 1.)
+     */
+    pub fn lerp(&self, other: Color, t: f32) -> Color {
+        let t = t.clamp(0., 1.);
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Color {
+            r: mix(self.r, other.r),
+            g: mix(self.g, other.g),
+            b: mix(self.b, other.b),
+            a: mix(self.a, other.a),
+        }
+    }
+
+    /*
+     * over()
+     *
+     * @Brief : self drawn over below (Porter-Duff "over", with the alpha of both)
+     */
+    pub fn over(&self, below: Color) -> Color {
+        let sa = self.a as f32 / 255.;
+        let da = below.a as f32 / 255.;
+        let a = sa + da * (1. - sa);
+        if a <= 0. {
+            return Color::TRANSPARENT;
+        }
+        let mix = |s: u8, d: u8| ((s as f32 * sa + d as f32 * da * (1. - sa)) / a).round() as u8;
+        Color::rgba(mix(self.r, below.r), mix(self.g, below.g), mix(self.b, below.b), (a * 255.).round() as u8)
+    }
+
+    pub fn with_alpha(&self, a: u8) -> Color {
+        Color { a, ..*self }
+    }
+
+    /*
+     * lighten()
+     *
+     * @Brief : Raise the lightness (HSL) by amount, from 0. to 1.
+     */
+    pub fn lighten(&self, amount: f32) -> Color {
+        let (h, s, l) = self.to_hsl();
+        Color::from_hsl(h, s, l + amount).with_alpha(self.a)
+    }
+
+    /*
+     * darken()
+     *
+     * @Brief : Lower the lightness (HSL) by amount, from 0. to 1.
+     */
+    pub fn darken(&self, amount: f32) -> Color {
+        self.lighten(-amount)
+    }
+
+    //=======================================================================
+    //                             CONSTANTS
+    //=======================================================================
+    pub const TRANSPARENT: Color = Color::rgba(0, 0, 0, 0);
+
     pub const RED: Color = Color {
         r: 255,
         g: 0,
@@ -28,6 +241,7 @@ impl Color {
         a: 255,
     };
 
+    // Pure green, the CSS "lime" (the CSS "green" is darker)
     pub const GREEN: Color = Color {
         r: 0,
         g: 255,
@@ -55,4 +269,369 @@ impl Color {
         b: 255,
         a: 255,
     };
-}
\ No newline at end of file
+
+    // CSS named colors
+    pub const ALICE_BLUE: Color = Color::rgb(240, 248, 255);
+    pub const ANTIQUE_WHITE: Color = Color::rgb(250, 235, 215);
+    pub const AQUA: Color = Color::rgb(0, 255, 255);
+    pub const AQUAMARINE: Color = Color::rgb(127, 255, 212);
+    pub const AZURE: Color = Color::rgb(240, 255, 255);
+    pub const BEIGE: Color = Color::rgb(245, 245, 220);
+    pub const BISQUE: Color = Color::rgb(255, 228, 196);
+    pub const BLANCHED_ALMOND: Color = Color::rgb(255, 235, 205);
+    pub const BLUE_VIOLET: Color = Color::rgb(138, 43, 226);
+    pub const BROWN: Color = Color::rgb(165, 42, 42);
+    pub const BURLY_WOOD: Color = Color::rgb(222, 184, 135);
+    pub const CADET_BLUE: Color = Color::rgb(95, 158, 160);
+    pub const CHARTREUSE: Color = Color::rgb(127, 255, 0);
+    pub const CHOCOLATE: Color = Color::rgb(210, 105, 30);
+    pub const CORAL: Color = Color::rgb(255, 127, 80);
+    pub const CORNFLOWER_BLUE: Color = Color::rgb(100, 149, 237);
+    pub const CORNSILK: Color = Color::rgb(255, 248, 220);
+    pub const CRIMSON: Color = Color::rgb(220, 20, 60);
+    pub const CYAN: Color = Color::rgb(0, 255, 255);
+    pub const DARK_BLUE: Color = Color::rgb(0, 0, 139);
+    pub const DARK_CYAN: Color = Color::rgb(0, 139, 139);
+    pub const DARK_GOLDENROD: Color = Color::rgb(184, 134, 11);
+    pub const DARK_GRAY: Color = Color::rgb(169, 169, 169);
+    pub const DARK_GREEN: Color = Color::rgb(0, 100, 0);
+    pub const DARK_GREY: Color = Color::rgb(169, 169, 169);
+    pub const DARK_KHAKI: Color = Color::rgb(189, 183, 107);
+    pub const DARK_MAGENTA: Color = Color::rgb(139, 0, 139);
+    pub const DARK_OLIVE_GREEN: Color = Color::rgb(85, 107, 47);
+    pub const DARK_ORANGE: Color = Color::rgb(255, 140, 0);
+    pub const DARK_ORCHID: Color = Color::rgb(153, 50, 204);
+    pub const DARK_RED: Color = Color::rgb(139, 0, 0);
+    pub const DARK_SALMON: Color = Color::rgb(233, 150, 122);
+    pub const DARK_SEA_GREEN: Color = Color::rgb(143, 188, 143);
+    pub const DARK_SLATE_BLUE: Color = Color::rgb(72, 61, 139);
+    pub const DARK_SLATE_GRAY: Color = Color::rgb(47, 79, 79);
+    pub const DARK_SLATE_GREY: Color = Color::rgb(47, 79, 79);
+    pub const DARK_TURQUOISE: Color = Color::rgb(0, 206, 209);
+    pub const DARK_VIOLET: Color = Color::rgb(148, 0, 211);
+    pub const DEEP_PINK: Color = Color::rgb(255, 20, 147);
+    pub const DEEP_SKY_BLUE: Color = Color::rgb(0, 191, 255);
+    pub const DIM_GRAY: Color = Color::rgb(105, 105, 105);
+    pub const DIM_GREY: Color = Color::rgb(105, 105, 105);
+    pub const DODGER_BLUE: Color = Color::rgb(30, 144, 255);
+    pub const FIRE_BRICK: Color = Color::rgb(178, 34, 34);
+    pub const FLORAL_WHITE: Color = Color::rgb(255, 250, 240);
+    pub const FOREST_GREEN: Color = Color::rgb(34, 139, 34);
+    pub const FUCHSIA: Color = Color::rgb(255, 0, 255);
+    pub const GAINSBORO: Color = Color::rgb(220, 220, 220);
+    pub const GHOST_WHITE: Color = Color::rgb(248, 248, 255);
+    pub const GOLD: Color = Color::rgb(255, 215, 0);
+    pub const GOLDENROD: Color = Color::rgb(218, 165, 32);
+    pub const GRAY: Color = Color::rgb(128, 128, 128);
+    pub const GREY: Color = Color::rgb(128, 128, 128);
+    pub const GREEN_YELLOW: Color = Color::rgb(173, 255, 47);
+    pub const HONEYDEW: Color = Color::rgb(240, 255, 240);
+    pub const HOT_PINK: Color = Color::rgb(255, 105, 180);
+    pub const INDIAN_RED: Color = Color::rgb(205, 92, 92);
+    pub const INDIGO: Color = Color::rgb(75, 0, 130);
+    pub const IVORY: Color = Color::rgb(255, 255, 240);
+    pub const KHAKI: Color = Color::rgb(240, 230, 140);
+    pub const LAVENDER: Color = Color::rgb(230, 230, 250);
+    pub const LAVENDER_BLUSH: Color = Color::rgb(255, 240, 245);
+    pub const LAWN_GREEN: Color = Color::rgb(124, 252, 0);
+    pub const LEMON_CHIFFON: Color = Color::rgb(255, 250, 205);
+    pub const LIGHT_BLUE: Color = Color::rgb(173, 216, 230);
+    pub const LIGHT_CORAL: Color = Color::rgb(240, 128, 128);
+    pub const LIGHT_CYAN: Color = Color::rgb(224, 255, 255);
+    pub const LIGHT_GOLDENROD_YELLOW: Color = Color::rgb(250, 250, 210);
+    pub const LIGHT_GRAY: Color = Color::rgb(211, 211, 211);
+    pub const LIGHT_GREEN: Color = Color::rgb(144, 238, 144);
+    pub const LIGHT_GREY: Color = Color::rgb(211, 211, 211);
+    pub const LIGHT_PINK: Color = Color::rgb(255, 182, 193);
+    pub const LIGHT_SALMON: Color = Color::rgb(255, 160, 122);
+    pub const LIGHT_SEA_GREEN: Color = Color::rgb(32, 178, 170);
+    pub const LIGHT_SKY_BLUE: Color = Color::rgb(135, 206, 250);
+    pub const LIGHT_SLATE_GRAY: Color = Color::rgb(119, 136, 153);
+    pub const LIGHT_SLATE_GREY: Color = Color::rgb(119, 136, 153);
+    pub const LIGHT_STEEL_BLUE: Color = Color::rgb(176, 196, 222);
+    pub const LIGHT_YELLOW: Color = Color::rgb(255, 255, 224);
+    pub const LIME: Color = Color::rgb(0, 255, 0);
+    pub const LIME_GREEN: Color = Color::rgb(50, 205, 50);
+    pub const LINEN: Color = Color::rgb(250, 240, 230);
+    pub const MAGENTA: Color = Color::rgb(255, 0, 255);
+    pub const MAROON: Color = Color::rgb(128, 0, 0);
+    pub const MEDIUM_AQUAMARINE: Color = Color::rgb(102, 205, 170);
+    pub const MEDIUM_BLUE: Color = Color::rgb(0, 0, 205);
+    pub const MEDIUM_ORCHID: Color = Color::rgb(186, 85, 211);
+    pub const MEDIUM_PURPLE: Color = Color::rgb(147, 112, 219);
+    pub const MEDIUM_SEA_GREEN: Color = Color::rgb(60, 179, 113);
+    pub const MEDIUM_SLATE_BLUE: Color = Color::rgb(123, 104, 238);
+    pub const MEDIUM_SPRING_GREEN: Color = Color::rgb(0, 250, 154);
+    pub const MEDIUM_TURQUOISE: Color = Color::rgb(72, 209, 204);
+    pub const MEDIUM_VIOLET_RED: Color = Color::rgb(199, 21, 133);
+    pub const MIDNIGHT_BLUE: Color = Color::rgb(25, 25, 112);
+    pub const MINT_CREAM: Color = Color::rgb(245, 255, 250);
+    pub const MISTY_ROSE: Color = Color::rgb(255, 228, 225);
+    pub const MOCCASIN: Color = Color::rgb(255, 228, 181);
+    pub const NAVAJO_WHITE: Color = Color::rgb(255, 222, 173);
+    pub const NAVY: Color = Color::rgb(0, 0, 128);
+    pub const OLD_LACE: Color = Color::rgb(253, 245, 230);
+    pub const OLIVE: Color = Color::rgb(128, 128, 0);
+    pub const OLIVE_DRAB: Color = Color::rgb(107, 142, 35);
+    pub const ORANGE: Color = Color::rgb(255, 165, 0);
+    pub const ORANGE_RED: Color = Color::rgb(255, 69, 0);
+    pub const ORCHID: Color = Color::rgb(218, 112, 214);
+    pub const PALE_GOLDENROD: Color = Color::rgb(238, 232, 170);
+    pub const PALE_GREEN: Color = Color::rgb(152, 251, 152);
+    pub const PALE_TURQUOISE: Color = Color::rgb(175, 238, 238);
+    pub const PALE_VIOLET_RED: Color = Color::rgb(219, 112, 147);
+    pub const PAPAYA_WHIP: Color = Color::rgb(255, 239, 213);
+    pub const PEACH_PUFF: Color = Color::rgb(255, 218, 185);
+    pub const PERU: Color = Color::rgb(205, 133, 63);
+    pub const PINK: Color = Color::rgb(255, 192, 203);
+    pub const PLUM: Color = Color::rgb(221, 160, 221);
+    pub const POWDER_BLUE: Color = Color::rgb(176, 224, 230);
+    pub const PURPLE: Color = Color::rgb(128, 0, 128);
+    pub const REBECCA_PURPLE: Color = Color::rgb(102, 51, 153);
+    pub const ROSY_BROWN: Color = Color::rgb(188, 143, 143);
+    pub const ROYAL_BLUE: Color = Color::rgb(65, 105, 225);
+    pub const SADDLE_BROWN: Color = Color::rgb(139, 69, 19);
+    pub const SALMON: Color = Color::rgb(250, 128, 114);
+    pub const SANDY_BROWN: Color = Color::rgb(244, 164, 96);
+    pub const SEA_GREEN: Color = Color::rgb(46, 139, 87);
+    pub const SEA_SHELL: Color = Color::rgb(255, 245, 238);
+    pub const SIENNA: Color = Color::rgb(160, 82, 45);
+    pub const SILVER: Color = Color::rgb(192, 192, 192);
+    pub const SKY_BLUE: Color = Color::rgb(135, 206, 235);
+    pub const SLATE_BLUE: Color = Color::rgb(106, 90, 205);
+    pub const SLATE_GRAY: Color = Color::rgb(112, 128, 144);
+    pub const SLATE_GREY: Color = Color::rgb(112, 128, 144);
+    pub const SNOW: Color = Color::rgb(255, 250, 250);
+    pub const SPRING_GREEN: Color = Color::rgb(0, 255, 127);
+    pub const STEEL_BLUE: Color = Color::rgb(70, 130, 180);
+    pub const TAN: Color = Color::rgb(210, 180, 140);
+    pub const TEAL: Color = Color::rgb(0, 128, 128);
+    pub const THISTLE: Color = Color::rgb(216, 191, 216);
+    pub const TOMATO: Color = Color::rgb(255, 99, 71);
+    pub const TURQUOISE: Color = Color::rgb(64, 224, 208);
+    pub const VIOLET: Color = Color::rgb(238, 130, 238);
+    pub const WHEAT: Color = Color::rgb(245, 222, 179);
+    pub const WHITE_SMOKE: Color = Color::rgb(245, 245, 245);
+    pub const YELLOW: Color = Color::rgb(255, 255, 0);
+    pub const YELLOW_GREEN: Color = Color::rgb(154, 205, 50);
+}
+
+fn to_u8(value: f32) -> u8 {
+    (value.clamp(0., 1.) * 255.).round() as u8
+}
+
+// Red, green and blue of a hue in degrees, for a chroma c
+fn hue_to_rgb(h: f32, c: f32) -> (f32, f32, f32) {
+    let h = h.rem_euclid(360.) / 60.;
+    let x = c * (1. - (h % 2. - 1.).abs());
+    match h as u32 {
+        0 => (c, x, 0.),
+        1 => (x, c, 0.),
+        2 => (0., c, x),
+        3 => (0., x, c),
+        4 => (x, 0., c),
+        _ => (c, 0., x),
+    }
+}
+
+// Hue in degrees of components from 0. to 1.
+fn hue(r: f32, g: f32, b: f32, max: f32, min: f32) -> f32 {
+    let delta = max - min;
+    if delta <= 0. {
+        return 0.;
+    }
+    let h = if max == r {
+        ((g - b) / delta).rem_euclid(6.)
+    } else if max == g {
+        (b - r) / delta + 2.
+    } else {
+        (r - g) / delta + 4.
+    };
+    h * 60.
+}
+
+// Sorted by name for from_name()
+const NAMED_COLORS: &[(&str, Color)] = &[
+    ("aliceblue", Color::ALICE_BLUE),
+    ("antiquewhite", Color::ANTIQUE_WHITE),
+    ("aqua", Color::AQUA),
+    ("aquamarine", Color::AQUAMARINE),
+    ("azure", Color::AZURE),
+    ("beige", Color::BEIGE),
+    ("bisque", Color::BISQUE),
+    ("black", Color::BLACK),
+    ("blanchedalmond", Color::BLANCHED_ALMOND),
+    ("blue", Color::BLUE),
+    ("blueviolet", Color::BLUE_VIOLET),
+    ("brown", Color::BROWN),
+    ("burlywood", Color::BURLY_WOOD),
+    ("cadetblue", Color::CADET_BLUE),
+    ("chartreuse", Color::CHARTREUSE),
+    ("chocolate", Color::CHOCOLATE),
+    ("coral", Color::CORAL),
+    ("cornflowerblue", Color::CORNFLOWER_BLUE),
+    ("cornsilk", Color::CORNSILK),
+    ("crimson", Color::CRIMSON),
+    ("cyan", Color::CYAN),
+    ("darkblue", Color::DARK_BLUE),
+    ("darkcyan", Color::DARK_CYAN),
+    ("darkgoldenrod", Color::DARK_GOLDENROD),
+    ("darkgray", Color::DARK_GRAY),
+    ("darkgreen", Color::DARK_GREEN),
+    ("darkgrey", Color::DARK_GREY),
+    ("darkkhaki", Color::DARK_KHAKI),
+    ("darkmagenta", Color::DARK_MAGENTA),
+    ("darkolivegreen", Color::DARK_OLIVE_GREEN),
+    ("darkorange", Color::DARK_ORANGE),
+    ("darkorchid", Color::DARK_ORCHID),
+    ("darkred", Color::DARK_RED),
+    ("darksalmon", Color::DARK_SALMON),
+    ("darkseagreen", Color::DARK_SEA_GREEN),
+    ("darkslateblue", Color::DARK_SLATE_BLUE),
+    ("darkslategray", Color::DARK_SLATE_GRAY),
+    ("darkslategrey", Color::DARK_SLATE_GREY),
+    ("darkturquoise", Color::DARK_TURQUOISE),
+    ("darkviolet", Color::DARK_VIOLET),
+    ("deeppink", Color::DEEP_PINK),
+    ("deepskyblue", Color::DEEP_SKY_BLUE),
+    ("dimgray", Color::DIM_GRAY),
+    ("dimgrey", Color::DIM_GREY),
+    ("dodgerblue", Color::DODGER_BLUE),
+    ("firebrick", Color::FIRE_BRICK),
+    ("floralwhite", Color::FLORAL_WHITE),
+    ("forestgreen", Color::FOREST_GREEN),
+    ("fuchsia", Color::FUCHSIA),
+    ("gainsboro", Color::GAINSBORO),
+    ("ghostwhite", Color::GHOST_WHITE),
+    ("gold", Color::GOLD),
+    ("goldenrod", Color::GOLDENROD),
+    ("gray", Color::GRAY),
+    ("green", Color::rgb(0, 128, 0)),
+    ("greenyellow", Color::GREEN_YELLOW),
+    ("grey", Color::GREY),
+    ("honeydew", Color::HONEYDEW),
+    ("hotpink", Color::HOT_PINK),
+    ("indianred", Color::INDIAN_RED),
+    ("indigo", Color::INDIGO),
+    ("ivory", Color::IVORY),
+    ("khaki", Color::KHAKI),
+    ("lavender", Color::LAVENDER),
+    ("lavenderblush", Color::LAVENDER_BLUSH),
+    ("lawngreen", Color::LAWN_GREEN),
+    ("lemonchiffon", Color::LEMON_CHIFFON),
+    ("lightblue", Color::LIGHT_BLUE),
+    ("lightcoral", Color::LIGHT_CORAL),
+    ("lightcyan", Color::LIGHT_CYAN),
+    ("lightgoldenrodyellow", Color::LIGHT_GOLDENROD_YELLOW),
+    ("lightgray", Color::LIGHT_GRAY),
+    ("lightgreen", Color::LIGHT_GREEN),
+    ("lightgrey", Color::LIGHT_GREY),
+    ("lightpink", Color::LIGHT_PINK),
+    ("lightsalmon", Color::LIGHT_SALMON),
+    ("lightseagreen", Color::LIGHT_SEA_GREEN),
+    ("lightskyblue", Color::LIGHT_SKY_BLUE),
+    ("lightslategray", Color::LIGHT_SLATE_GRAY),
+    ("lightslategrey", Color::LIGHT_SLATE_GREY),
+    ("lightsteelblue", Color::LIGHT_STEEL_BLUE),
+    ("lightyellow", Color::LIGHT_YELLOW),
+    ("lime", Color::LIME),
+    ("limegreen", Color::LIME_GREEN),
+    ("linen", Color::LINEN),
+    ("magenta", Color::MAGENTA),
+    ("maroon", Color::MAROON),
+    ("mediumaquamarine", Color::MEDIUM_AQUAMARINE),
+    ("mediumblue", Color::MEDIUM_BLUE),
+    ("mediumorchid", Color::MEDIUM_ORCHID),
+    ("mediumpurple", Color::MEDIUM_PURPLE),
+    ("mediumseagreen", Color::MEDIUM_SEA_GREEN),
+    ("mediumslateblue", Color::MEDIUM_SLATE_BLUE),
+    ("mediumspringgreen", Color::MEDIUM_SPRING_GREEN),
+    ("mediumturquoise", Color::MEDIUM_TURQUOISE),
+    ("mediumvioletred", Color::MEDIUM_VIOLET_RED),
+    ("midnightblue", Color::MIDNIGHT_BLUE),
+    ("mintcream", Color::MINT_CREAM),
+    ("mistyrose", Color::MISTY_ROSE),
+    ("moccasin", Color::MOCCASIN),
+    ("navajowhite", Color::NAVAJO_WHITE),
+    ("navy", Color::NAVY),
+    ("oldlace", Color::OLD_LACE),
+    ("olive", Color::OLIVE),
+    ("olivedrab", Color::OLIVE_DRAB),
+    ("orange", Color::ORANGE),
+    ("orangered", Color::ORANGE_RED),
+    ("orchid", Color::ORCHID),
+    ("palegoldenrod", Color::PALE_GOLDENROD),
+    ("palegreen", Color::PALE_GREEN),
+    ("paleturquoise", Color::PALE_TURQUOISE),
+    ("palevioletred", Color::PALE_VIOLET_RED),
+    ("papayawhip", Color::PAPAYA_WHIP),
+    ("peachpuff", Color::PEACH_PUFF),
+    ("peru", Color::PERU),
+    ("pink", Color::PINK),
+    ("plum", Color::PLUM),
+    ("powderblue", Color::POWDER_BLUE),
+    ("purple", Color::PURPLE),
+    ("rebeccapurple", Color::REBECCA_PURPLE),
+    ("red", Color::RED),
+    ("rosybrown", Color::ROSY_BROWN),
+    ("royalblue", Color::ROYAL_BLUE),
+    ("saddlebrown", Color::SADDLE_BROWN),
+    ("salmon", Color::SALMON),
+    ("sandybrown", Color::SANDY_BROWN),
+    ("seagreen", Color::SEA_GREEN),
+    ("seashell", Color::SEA_SHELL),
+    ("sienna", Color::SIENNA),
+    ("silver", Color::SILVER),
+    ("skyblue", Color::SKY_BLUE),
+    ("slateblue", Color::SLATE_BLUE),
+    ("slategray", Color::SLATE_GRAY),
+    ("slategrey", Color::SLATE_GREY),
+    ("snow", Color::SNOW),
+    ("springgreen", Color::SPRING_GREEN),
+    ("steelblue", Color::STEEL_BLUE),
+    ("tan", Color::TAN),
+    ("teal", Color::TEAL),
+    ("thistle", Color::THISTLE),
+    ("tomato", Color::TOMATO),
+    ("transparent", Color::TRANSPARENT),
+    ("turquoise", Color::TURQUOISE),
+    ("violet", Color::VIOLET),
+    ("wheat", Color::WHEAT),
+    ("white", Color::WHITE),
+    ("whitesmoke", Color::WHITE_SMOKE),
+    ("yellow", Color::YELLOW),
+    ("yellowgreen", Color::YELLOW_GREEN),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_hex_formats() {
+        assert_eq!(Color::from_hex("#ff8000").unwrap(), Color::rgb(255, 128, 0));
+        assert_eq!(Color::from_hex("ff800080").unwrap(), Color::rgba(255, 128, 0, 128));
+        assert_eq!(Color::from_hex("#f80").unwrap(), Color::rgb(255, 136, 0));
+        assert_eq!(Color::from_hex(" #f808 ").unwrap(), Color::rgba(255, 136, 0, 136));
+    }
+
+    #[test]
+    fn from_hex_errors() {
+        for hex in ["", "#", "#12", "#12345", "#gggggg", "#ff80é0"] {
+            assert!(Color::from_hex(hex).is_err(), "{}", hex);
+        }
+    }
+
+    #[test]
+    fn to_hex_round_trip() {
+        let color = Color::rgba(1, 35, 69, 103);
+        assert_eq!(Color::from_hex(&color.to_hex()).unwrap(), color);
+    }
+
+    #[test]
+    fn over_uses_both_alphas() {
+        assert_eq!(Color::rgba(255, 0, 0, 128).over(Color::TRANSPARENT), Color::rgba(255, 0, 0, 128));
+        assert_eq!(Color::TRANSPARENT.over(Color::TRANSPARENT), Color::TRANSPARENT);
+        assert_eq!(Color::RED.over(Color::rgb(0, 0, 255)), Color::RED);
+    }
+}
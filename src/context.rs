@@ -1,15 +1,33 @@
-use sdl2::Sdl;
+use std::sync::OnceLock;
 
+use sdl2::{ttf::Sdl2TtfContext, Sdl};
 
+use crate::gc2d::Gc2dResult;
+
+// SDL_ttf, initialized once for the program : the fonts borrow it for their
+// whole life, and all the graphics share it
+static TTF_CONTEXT: OnceLock<Result<Sdl2TtfContext, String>> = OnceLock::new();
 
 pub struct Context {
     pub context: Sdl,
+    // The error is returned by the first font loaded
+    ttf_context: Result<&'static Sdl2TtfContext, String>,
 }
 
 impl Context {
     pub fn new() -> Self {
+        let ttf_context = TTF_CONTEXT
+            .get_or_init(|| sdl2::ttf::init().map_err(|e| e.to_string()))
+            .as_ref()
+            .map_err(|e| format!("Fonts are not available: {}", e));
+
         Self {
             context: sdl2::init().unwrap(),
+            ttf_context,
         }
     }
-}
\ No newline at end of file
+
+    pub(crate) fn get_ttf_context(&self) -> Gc2dResult<&'static Sdl2TtfContext> {
+        self.ttf_context.clone()
+    }
+}
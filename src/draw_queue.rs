@@ -0,0 +1,139 @@
+use crate::{gc2d::Gc2dResult, graphics::{DrawState, Graphics}};
+
+/*================================================================
+ *                      D R A W   Q U E U E
+ *================================================================*/
+/*
+ * In deferred mode the draw calls of a frame are recorded with the layer and
+ * the depth set before them, then sorted and drawn by end_draw() : layers by
+ * order, then depth (lowest first), then call order.
+ *
+ *   graphics.set_deferred(true);
+ *   graphics.new_layer("ui", 10, false);
+ *
+ *   graphics.set_layer("default")?;
+ *   for enemy in enemies { graphics.set_depth(enemy.y); enemy.draw(graphics)?; }
+ *   graphics.set_layer("ui")?;
+ *   graphics.print(...);
+ */
+pub(crate) type DrawCommand = Box<dyn FnOnce(&mut Graphics) -> Gc2dResult<()>>;
+
+// Name of the layer which exists from the start
+pub const DEFAULT_LAYER: &str = "default";
+
+#[derive(Clone, Debug)]
+pub struct Layer {
+    pub name: String,
+    pub order: i32,
+    // False : drawn without the transformation of graphics (ex : the UI)
+    pub transform: bool,
+}
+
+struct QueuedDraw {
+    layer: usize,
+    depth: f32,
+    state: DrawState,
+    command: DrawCommand,
+}
+
+pub(crate) struct DrawQueue {
+    pub enabled: bool,
+    layers: Vec<Layer>,
+    commands: Vec<QueuedDraw>,
+    actual_layer: usize,
+    actual_depth: f32,
+}
+
+impl DrawQueue {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            layers: vec![Layer { name: String::from(DEFAULT_LAYER), order: 0, transform: true }],
+            commands: Vec::new(),
+            actual_layer: 0,
+            actual_depth: 0.,
+        }
+    }
+
+    //=======================================================================
+    //                             LAYERS
+    //=======================================================================
+    /*
+     * new_layer()
+     *
+     * @Brief : Add a layer, or change the order and transform of an existing one
+     */
+    pub fn new_layer(&mut self, name: &str, order: i32, transform: bool) {
+        match self.layers.iter_mut().find(|layer| layer.name == name) {
+            Some(layer) => {
+                layer.order = order;
+                layer.transform = transform;
+            },
+            None => self.layers.push(Layer { name: name.to_string(), order, transform }),
+        }
+    }
+
+    pub fn set_layer(&mut self, name: &str) -> Gc2dResult<()> {
+        self.actual_layer = self
+            .layers
+            .iter()
+            .position(|layer| layer.name == name)
+            .ok_or_else(|| format!("Layer \"{}\" does not exist", name))?;
+        Ok(())
+    }
+
+    pub fn get_layer(&self) -> &Layer {
+        &self.layers[self.actual_layer]
+    }
+
+    pub fn get_layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    pub fn set_depth(&mut self, depth: f32) {
+        self.actual_depth = depth;
+    }
+
+    pub fn get_depth(&self) -> f32 {
+        self.actual_depth
+    }
+
+    //=======================================================================
+    //                             QUEUE
+    //=======================================================================
+    pub fn push(&mut self, state: DrawState, command: DrawCommand) {
+        self.commands.push(QueuedDraw {
+            layer: self.actual_layer,
+            depth: self.actual_depth,
+            state,
+            command,
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /*
+     * take_sorted()
+     *
+     * @Brief : Empty the queue, the draws in drawing order with the
+     *          transform setting of their layer
+     */
+    pub fn take_sorted(&mut self) -> Vec<(bool, DrawState, DrawCommand)> {
+        let mut commands: Vec<QueuedDraw> = self.commands.drain(..).collect();
+
+        // Stable : equal keys keep the call order
+        let layers = &self.layers;
+        commands.sort_by(|a, b| {
+            layers[a.layer].order
+                .cmp(&layers[b.layer].order)
+                .then(a.depth.total_cmp(&b.depth))
+        });
+
+        commands
+            .into_iter()
+            .map(|draw| (self.layers[draw.layer].transform, draw.state, draw.command))
+            .collect()
+    }
+}
@@ -1,7 +1,7 @@
 
 use sdl2::EventPump;
 
-use crate::{gc2d::{Gc2d, Gc2dResult}, context::Context, fonts::FontsManager, keyboard::KeyCode, audio::AudioManager};
+use crate::{gc2d::{Gc2d, Gc2dResult}, context::Context, keyboard::KeyCode, audio::AudioManager};
 
 #[allow(unused_variables)]
 pub trait EventLoop {
@@ -9,7 +9,7 @@ pub trait EventLoop {
         Ok(())
     }
 
-    fn draw(&mut self, gc2d: &mut Gc2d, fonts: &mut FontsManager, dt: f32) -> Gc2dResult<()> {
+    fn draw(&mut self, gc2d: &mut Gc2d, dt: f32) -> Gc2dResult<()> {
         Ok(())
     }
 
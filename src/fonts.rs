@@ -1,16 +1,61 @@
 
 use std::collections::HashMap;
-use sdl2::{render::{Texture, TextureCreator}, ttf::Sdl2TtfContext};
+use sdl2::{pixels::PixelFormatEnum, rect::Rect, render::{BlendMode, Texture, TextureCreator}, ttf::Sdl2TtfContext};
 
-use crate::{color::Color, gc2d::Gc2dResult};
+use crate::{bitmap_font::BitmapFont, color::Color, gc2d::Gc2dResult, texture_cache::destroy_texture};
 
 pub type FontContext<'a> = sdl2::ttf::Sdl2TtfContext;
 pub type FontStyle = sdl2::ttf::FontStyle;
 
+/*
+ * Font : A TrueType font (.ttf) at a size, or a bitmap font (a BMFont .fnt,
+ *        or an image font of graphics.new_image_font()) drawn at its own size
+ *
+ * A font can be drawn with a style and an outline without loading its file
+ * again, the fonts of a file and a size share one loaded font :
+ *
+ *   let title = graphics.new_font("font.ttf", 32)
+ *       .with_style(FontStyle::BOLD | FontStyle::UNDERLINE)
+ *       .with_outline(2, Color::BLACK);
+ *
+ * Bitmap fonts have no style and no outline.
+ */
 #[derive(Hash, PartialEq, Eq, Debug, Clone)]
 pub struct Font {
     pub filename: String,
     pub point_size: u16,
+    pub style: FontStyle,
+    // Thickness in pixels around the glyphs, 0 : no outline
+    pub outline: u16,
+    pub outline_color: Color,
+}
+
+impl Font {
+    pub(crate) fn new(filename: &str, point_size: u16) -> Self {
+        Self {
+            filename: String::from(filename),
+            point_size,
+            style: FontStyle::NORMAL,
+            outline: 0,
+            outline_color: Color::BLACK,
+        }
+    }
+
+    pub fn with_style(mut self, style: FontStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn with_outline(mut self, thickness: u16, color: Color) -> Self {
+        self.outline = thickness;
+        self.outline_color = color;
+        self
+    }
+
+    // Key of the loaded file, the same for all the styles
+    fn file_key(&self) -> (String, u16) {
+        (self.filename.clone(), self.point_size)
+    }
 }
 
 pub struct FontSize {
@@ -19,53 +64,111 @@ pub struct FontSize {
 }
 
 pub struct FontsManager<'ttf, 'rwops: 'ttf> {
-    fonts: HashMap<Font, Box<sdl2::ttf::Font<'ttf, 'rwops>>>,
+    fonts: HashMap<(String, u16), Box<sdl2::ttf::Font<'ttf, 'rwops>>>,
+    bitmap_fonts: HashMap<(String, u16), BitmapFont>,
 }
 
 impl<'ttf, 'rwops> FontsManager<'ttf, 'rwops> {
     pub(crate) fn new() -> Self {
         Self {
           fonts: HashMap::new(),
+          bitmap_fonts: HashMap::new(),
         }
     }
 
-    pub(crate) fn new_font(&mut self, ttf_context: &'ttf Sdl2TtfContext, font_key: Font) -> Gc2dResult<()> {
+    // The TTF context is only needed for TrueType fonts, a BMFont loads without it
+    pub(crate) fn new_font(&mut self, ttf_context: Gc2dResult<&'ttf Sdl2TtfContext>, font_key: Font) -> Gc2dResult<()> {
+
+      // AngelCode BMFont
+      if font_key.filename.to_ascii_lowercase().ends_with(".fnt") {
+        let font = BitmapFont::from_bmfont(&font_key.filename)?;
+        self.bitmap_fonts.insert(font_key.file_key(), font);
+        return Ok(());
+      }
      
-      let font = ttf_context.load_font(font_key.filename.clone(), font_key.point_size)?;
+      let font = ttf_context?.load_font(font_key.filename.clone(), font_key.point_size)?;
       
       self.fonts.insert(
-        font_key, 
+        font_key.file_key(), 
         Box::new(font),
       );
 
       Ok(())
     }
 
-    pub(crate) fn get_texture(&mut self, texture_creator: &TextureCreator<sdl2::video::WindowContext>, font_key: &Font, texte: String, color: &Color) -> Option<Texture> {
-
-      if let Some(detail) = self.fonts.get_mut(&font_key) {
-        
-        let font = detail.as_mut();
-
-        font.set_style(FontStyle::NORMAL);
-
-        if let Ok(surface) = font
-            .render(texte.as_str())
-            .blended(Color::to_sdl_color(color)) {
+    /*
+     * new_image_font()
+     *
+     * @Brief : Load an image font, glyphs are its characters from left to right
+     */
+    pub(crate) fn new_image_font(&mut self, font_key: Font, glyphs: &str) -> Gc2dResult<()> {
+      let font = BitmapFont::from_image(&font_key.filename, glyphs)?;
+      self.bitmap_fonts.insert(font_key.file_key(), font);
+      Ok(())
+    }
 
-          if let Ok(texture) = 
-              texture_creator
-              .create_texture_from_surface(&surface) {
-            return Some(texture);
-          }
-        }  
+    // Loaded TrueType font of a font key
+    fn ttf(&mut self, font_key: &Font) -> Gc2dResult<&mut sdl2::ttf::Font<'ttf, 'rwops>> {
+      self.fonts
+          .get_mut(&font_key.file_key())
+          .map(|font| font.as_mut())
+          .ok_or_else(|| format!("Font {} ({}) is not loaded", font_key.filename, font_key.point_size))
+    }
 
+    /*
+     * get_texture()
+     *
+     * @Brief : Render a text in a new texture (the caller has to destroy it),
+     *          style is added to the style of the font
+     *          Bitmap fonts have no styles
+     */
+    pub(crate) fn get_texture(&mut self, texture_creator: &TextureCreator<sdl2::video::WindowContext>, font_key: &Font, texte: &str, color: Color, style: FontStyle) -> Gc2dResult<Texture> {
+
+      if let Some(font) = self.bitmap_fonts.get(&font_key.file_key()) {
+        let data = font.render(texte, color);
+        let mut texture = texture_creator
+            .create_texture_static(PixelFormatEnum::RGBA32, data.get_width(), data.get_height())
+            .map_err(|e| e.to_string())?;
+        if let Err(e) = texture.update(None, data.get_pixels(), data.get_width() as usize * 4) {
+          destroy_texture(texture);
+          return Err(e.to_string());
+        }
+        texture.set_blend_mode(BlendMode::Blend);
+        return Ok(texture);
       }
 
-      return Option::None;
+      let font = self.ttf(font_key)?;
+      font.set_style(font_key.style | style);
+
+      let surface = if font_key.outline > 0 {
+        // The outlined glyphs are larger by the thickness on each side :
+        // the text is drawn over them, moved by the thickness
+        font.set_outline_width(font_key.outline);
+        let outline = font.render(texte).blended(font_key.outline_color.to_sdl_color());
+        font.set_outline_width(0);
+        let text = font.render(texte).blended(color.to_sdl_color());
+
+        match (outline, text) {
+          (Ok(mut outline), Ok(text)) => {
+            let offset = font_key.outline as i32;
+            text
+                .blit(None, &mut outline, Rect::new(offset, offset, text.width(), text.height()))
+                .map(|_| outline)
+          },
+          (Err(e), _) | (_, Err(e)) => Err(e.to_string()),
+        }
+      } else {
+        font.render(texte).blended(color.to_sdl_color()).map_err(|e| e.to_string())
+      };
+      font.set_style(FontStyle::NORMAL);
+      let surface = surface?;
+
+      texture_creator
+          .create_texture_from_surface(&surface)
+          .map_err(|e| e.to_string())
     }
 
-    pub(crate) fn get_font_height(&self, text: &str, font_key: &Font) -> Gc2dResult<u32> {
+    pub(crate) fn get_font_height(&mut self, text: &str, font_key: &Font) -> Gc2dResult<u32> {
       if let Ok(size) = self.get_font_size(text, font_key) {
         Ok(size.height)
       } else {
@@ -73,7 +176,7 @@ impl<'ttf, 'rwops> FontsManager<'ttf, 'rwops> {
       }
     }
 
-    pub(crate) fn get_font_width(&self, text: &str, font_key: &Font) -> Gc2dResult<u32>  {
+    pub(crate) fn get_font_width(&mut self, text: &str, font_key: &Font) -> Gc2dResult<u32>  {
       if let Ok(size) = self.get_font_size(text, font_key) {
         Ok(size.width)
       } else {
@@ -81,16 +184,61 @@ impl<'ttf, 'rwops> FontsManager<'ttf, 'rwops> {
       }
     }
 
-    pub(crate) fn get_font_size(&self, text: &str, font_key: &Font) -> Gc2dResult<FontSize>  {
-      if let Some(detail) = self.fonts.get(&font_key) {
-        let font = detail.as_ref();
-        if let Ok(size) = font.size_of(text) {
-          return Ok(FontSize { height: size.1, width: size.0 });
-        } else {
-          return Err(String::from("Font size not found"));
-        }
+    /*
+     * get_line_height()
+     *
+     * @Brief : Distance between two lines of text, an outline adds its
+     *          thickness above and under the glyphs
+     */
+    pub(crate) fn get_line_height(&self, font_key: &Font) -> Gc2dResult<u32> {
+      if let Some(font) = self.bitmap_fonts.get(&font_key.file_key()) {
+        return Ok(font.get_line_height());
+      }
+      self.fonts
+          .get(&font_key.file_key())
+          .map(|font| font.recommended_line_spacing().max(0) as u32 + 2 * font_key.outline as u32)
+          .ok_or_else(|| format!("Font {} ({}) is not loaded", font_key.filename, font_key.point_size))
+    }
+
+    /*
+     * get_ascent()
+     *
+     * @Brief : Distance from the top of a line to the baseline
+     */
+    pub(crate) fn get_ascent(&self, font_key: &Font) -> Gc2dResult<u32> {
+      if let Some(font) = self.bitmap_fonts.get(&font_key.file_key()) {
+        return Ok(font.get_base());
       }
-      Err(String::from("Font not found"))
+      self.fonts
+          .get(&font_key.file_key())
+          .map(|font| font.ascent().max(0) as u32 + font_key.outline as u32)
+          .ok_or_else(|| format!("Font {} ({}) is not loaded", font_key.filename, font_key.point_size))
     }
 
-}
\ No newline at end of file
+    /*
+     * get_styled_size()
+     *
+     * @Brief : Size of a text rendered with a style added to the style of the
+     *          font (bold is wider), and its outline
+     */
+    pub(crate) fn get_styled_size(&mut self, text: &str, font_key: &Font, style: FontStyle) -> Gc2dResult<FontSize> {
+      if let Some(font) = self.bitmap_fonts.get(&font_key.file_key()) {
+        let (width, height) = font.size_of(text);
+        return Ok(FontSize { height, width });
+      }
+
+      let font = self.ttf(font_key)?;
+      font.set_style(font_key.style | style);
+      let size = font.size_of(text);
+      font.set_style(FontStyle::NORMAL);
+
+      let (width, height) = size.map_err(|e| e.to_string())?;
+      let outline = 2 * font_key.outline as u32;
+      Ok(FontSize { height: height + outline, width: width + outline })
+    }
+
+    pub(crate) fn get_font_size(&mut self, text: &str, font_key: &Font) -> Gc2dResult<FontSize>  {
+      self.get_styled_size(text, font_key, FontStyle::NORMAL)
+    }
+
+}
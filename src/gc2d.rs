@@ -1,6 +1,6 @@
 
 use std::time::{Instant, Duration};
-use crate::{window::Window, graphics::Graphics, event::{Event, EventLoop}, context::Context, fonts::FontsManager, keyboard::Keyboard, audio::AudioManager, mouse::Mouse};
+use crate::{window::Window, graphics::Graphics, event::{Event, EventLoop}, context::Context, keyboard::Keyboard, audio::AudioManager, mouse::Mouse};
 use crate::audio::Audio;
 
 pub type Gc2dResult<T> = Result<T, String>;
@@ -49,12 +49,6 @@ impl Gc2d {
     pub fn run(&mut self, mut game: impl EventLoop) -> Gc2dResult<()>{
 
 
-        // Initialize font context
-        let ttf_context = sdl2::ttf::init().unwrap();
-
-        // Create fonts manager
-        let mut fonts_manager = FontsManager::new();
-
         // Create audio manager
         let mut audio_manager: AudioManager = AudioManager::new();
     
@@ -68,21 +62,6 @@ impl Gc2d {
         // Main loop
         'mainloop: loop {
 
-            let mut font_clone = None;
-
-            // Add new fonts ?
-            for font in self.graphics._new_fonts.iter() {
-                fonts_manager.new_font(&ttf_context, font.clone()).unwrap();
-                if self.graphics.actual_font.is_none() && font_clone.is_none() {
-                    font_clone = Some(font.clone());
-                }
-            }
-            self.graphics._new_fonts.clear();
-
-            if self.graphics.actual_font.is_none() && font_clone.is_some() {
-                self.graphics.set_font(font_clone);
-            }
-
             // Before drawing
             self.graphics.begin_draw();
     
@@ -115,13 +94,14 @@ impl Gc2d {
             // Update
             let dt: f32 = timer_start.elapsed().as_secs_f32();
             timer_start = Instant::now();
+            self.graphics.update_transition(dt);
             game.update(self, dt, &mut audio_manager)?;
     
             // Drawing
-            game.draw(self, &mut fonts_manager, dt)?;
+            game.draw(self, dt)?;
     
             // End
-            self.graphics.end_draw();
+            self.graphics.end_draw()?;
             
             // Limit FPS (todo : not optimized ...)
             if self.max_fps > 0 {
@@ -0,0 +1,234 @@
+use crate::{color::Color, gc2d::Gc2dResult, image_data::ImageData};
+
+// Textures of drawn gradients kept at most (graphics.rectangle_gradient()...)
+pub(crate) const GRADIENT_CACHE_SIZE: usize = 16;
+
+/*================================================================
+ *                         G R A D I E N T
+ *================================================================*/
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GradientKind {
+    // Along a direction, in radians (0. = left to right, PI / 2. = top to bottom)
+    Linear(f32),
+    // From a center (normalized in the shape, 0.5, 0.5 = middle) to the farthest corner
+    Radial(f32, f32),
+}
+
+/*
+ * Gradient : Colors spread over a shape, stops are positions from 0. to 1.
+ *            The gradient covers the bounding box of the shape it fills
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Gradient {
+    pub kind: GradientKind,
+    stops: Vec<(f32, Color)>,
+}
+
+impl Gradient {
+    /*
+     * linear()
+     *
+     * @Brief : Colors spread evenly along a direction (radians)
+     */
+    pub fn linear(angle: f32, colors: &[Color]) -> Self {
+        Self {
+            kind: GradientKind::Linear(angle),
+            stops: Gradient::even_stops(colors),
+        }
+    }
+
+    pub fn vertical(top: Color, bottom: Color) -> Self {
+        Gradient::linear(std::f32::consts::FRAC_PI_2, &[top, bottom])
+    }
+
+    pub fn horizontal(left: Color, right: Color) -> Self {
+        Gradient::linear(0., &[left, right])
+    }
+
+    /*
+     * radial()
+     *
+     * @Brief : Colors spread evenly from the middle of the shape to its corners
+     */
+    pub fn radial(colors: &[Color]) -> Self {
+        Self {
+            kind: GradientKind::Radial(0.5, 0.5),
+            stops: Gradient::even_stops(colors),
+        }
+    }
+
+    /*
+     * with_stops()
+     *
+     * @Brief : Replace the colors by stops (position, color), sorted by position
+     */
+    pub fn with_stops(mut self, stops: &[(f32, Color)]) -> Self {
+        self.stops = stops.to_vec();
+        self.stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        self
+    }
+
+    pub fn with_center(mut self, x: f32, y: f32) -> Self {
+        if let GradientKind::Radial(..) = self.kind {
+            self.kind = GradientKind::Radial(x, y);
+        }
+        self
+    }
+
+    fn even_stops(colors: &[Color]) -> Vec<(f32, Color)> {
+        let last = colors.len().saturating_sub(1).max(1) as f32;
+        colors
+            .iter()
+            .enumerate()
+            .map(|(i, color)| (i as f32 / last, *color))
+            .collect()
+    }
+
+    /*
+     * color_at()
+     *
+     * @Brief : Color at a position from 0. to 1.
+     */
+    pub fn color_at(&self, t: f32) -> Color {
+        let (first, last) = match (self.stops.first(), self.stops.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Color::TRANSPARENT,
+        };
+
+        if t <= first.0 {
+            return first.1;
+        }
+        if t >= last.0 {
+            return last.1;
+        }
+
+        for pair in self.stops.windows(2) {
+            let ((t0, c0), (t1, c1)) = (pair[0], pair[1]);
+            if t <= t1 {
+                let f = if t1 > t0 { (t - t0) / (t1 - t0) } else { 1. };
+                return c0.lerp(c1, f);
+            }
+        }
+        last.1
+    }
+
+    // Projection of the points of a width x height box on the gradient
+    fn projection(&self, width: f32, height: f32) -> Projection {
+        let corners = [(0., 0.), (width, 0.), (0., height), (width, height)];
+        match self.kind {
+            GradientKind::Linear(angle) => {
+                // Projection on the direction, the box corners give 0. and 1.
+                let (dx, dy) = (angle.cos(), angle.sin());
+                let projections = corners.map(|(cx, cy)| cx * dx + cy * dy);
+                let min = projections.iter().cloned().fold(f32::MAX, f32::min);
+                let max = projections.iter().cloned().fold(f32::MIN, f32::max);
+                Projection::Linear { dx, dy, min, length: max - min }
+            },
+            GradientKind::Radial(cx, cy) => {
+                let (cx, cy) = (cx * width, cy * height);
+                let radius = corners
+                    .iter()
+                    .map(|(px, py)| ((px - cx) * (px - cx) + (py - cy) * (py - cy)).sqrt())
+                    .fold(0., f32::max);
+                Projection::Radial { cx, cy, radius }
+            },
+        }
+    }
+
+    /*
+     * to_image_data()
+     *
+     * @Brief : Fill a new image data with the gradient, ex : a sky background
+     */
+    pub fn to_image_data(&self, width: u32, height: u32) -> ImageData {
+        self.fill(width, height, |_, _| true)
+    }
+
+    /*
+     * fill()
+     *
+     * @Brief : Image data of the gradient where inside(x, y) is true, transparent elsewhere
+     */
+    pub(crate) fn fill<F: Fn(f32, f32) -> bool>(&self, width: u32, height: u32, inside: F) -> ImageData {
+        let projection = self.projection(width as f32, height as f32);
+        let mut data = ImageData::new(width, height);
+        data.map_pixels(|x, y, _| {
+            // Center of the pixel
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+            if inside(px, py) {
+                self.color_at(projection.position(px, py))
+            } else {
+                Color::TRANSPARENT
+            }
+        });
+        data
+    }
+}
+
+enum Projection {
+    Linear { dx: f32, dy: f32, min: f32, length: f32 },
+    Radial { cx: f32, cy: f32, radius: f32 },
+}
+
+impl Projection {
+    // Position in the gradient of a point (x, y) of the box
+    fn position(&self, x: f32, y: f32) -> f32 {
+        match *self {
+            Projection::Linear { dx, dy, min, length } => {
+                if length > 0. { (x * dx + y * dy - min) / length } else { 0. }
+            },
+            Projection::Radial { cx, cy, radius } => {
+                if radius > 0. { ((x - cx) * (x - cx) + (y - cy) * (y - cy)).sqrt() / radius } else { 0. }
+            },
+        }
+    }
+}
+
+/*
+ * point_in_polygon()
+ *
+ * @Brief : Even-odd rule
+ */
+pub(crate) fn point_in_polygon(x: f32, y: f32, points: &[(f32, f32)]) -> bool {
+    let mut inside = false;
+    let mut j = points.len().wrapping_sub(1);
+    for i in 0..points.len() {
+        let (xi, yi) = points[i];
+        let (xj, yj) = points[j];
+        if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+// Checked size of the bounding box of a shape, in screen pixels
+pub(crate) fn bounding_size(width: f32, height: f32) -> Gc2dResult<(u32, u32)> {
+    if !(width.is_finite() && height.is_finite()) || width > 16384. || height > 16384. {
+        return Err(format!("Gradient of {}x{} pixels is too large", width, height));
+    }
+    Ok((width.ceil().max(0.) as u32, height.ceil().max(0.) as u32))
+}
+
+//=======================================================================
+//                             CACHE
+//=======================================================================
+/*
+ * GradientShape : What a gradient fills, the points of a polygon are relative
+ *                 to its bounding box
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) enum GradientShape {
+    Rectangle,
+    Circle,
+    Polygon(Vec<(f32, f32)>),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) struct GradientKey {
+    pub gradient: Gradient,
+    pub shape: GradientShape,
+    pub width: u32,
+    pub height: u32,
+}
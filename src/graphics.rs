@@ -1,35 +1,222 @@
 
-use sdl2::{render::{Canvas, TextureCreator}, video::WindowContext};
-use crate::{context::Context, color::Color, fonts::{FontsManager, Font}, image::{ImageType, Image, ImagesManager, Quad}, gc2d::Gc2dResult};
+use sdl2::{gfx::primitives::DrawRenderer, render::{Canvas, TextureCreator}, ttf::Sdl2TtfContext, video::WindowContext};
+use crate::{context::Context, color::Color, draw_queue::{DrawQueue, Layer}, fonts::{FontsManager, Font, FontStyle}, gradient::{self, Gradient, GradientKey, GradientShape}, path::{self, LineCap, LineJoin, Path, Span}, image::{FilterMode, ImageType, Image, ImageHandle, ImageStats, ImagesManager, NineSlice, NineSliceMode, Quad}, image_data::ImageData, palette::Palette, rich_text::{LineMetrics, RichLayout, RichText, RunKind}, text::{self, Text, TextAlign, TextKey, TextRendering, TextShadow, TextsManager, WrappedText}, texture_cache::{destroy_texture, TextureCache}, transition::{Transition, TransitionKind}, gc2d::Gc2dResult};
 
 
 pub type FontsCreator = TextureCreator<WindowContext>;
 
-#[allow(dead_code)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum DrawMode {
     Fill,
     Line,
 }
 
+/*
+ * LineStyle : Rough lines are aliased, Smooth lines have anti-aliased edges
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LineStyle {
+    Rough,
+    Smooth,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BlendMode {
+    None,
+    Alpha,
+    Add,
+    Multiply,
+}
+
+impl BlendMode {
+    pub(crate) fn to_sdl_blend_mode(self) -> sdl2::render::BlendMode {
+        match self {
+            BlendMode::None => sdl2::render::BlendMode::None,
+            BlendMode::Alpha => sdl2::render::BlendMode::Blend,
+            BlendMode::Add => sdl2::render::BlendMode::Add,
+            BlendMode::Multiply => sdl2::render::BlendMode::Mod,
+        }
+    }
+}
+
+/*
+ * Origin : Point of the image placed at the draw position, and center of
+ *          rotation, scale and flips
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Origin {
+    // In pixels of the image (or of the quad)
+    Pixels(f32, f32),
+    // From (0, 0) top left to (1, 1) bottom right
+    Normalized(f32, f32),
+}
+
+/*
+ * DrawParams : How to draw an image or a text
+ *
+ * ex : DrawParams::new(x, y).with_rotation_degrees(45.).with_origin_normalized(0.5, 0.5)
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct DrawParams {
+    pub x: f32,
+    pub y: f32,
+    // Radians, clockwise
+    pub rotation: f32,
+    pub scale_x: f32,
+    pub scale_y: f32,
+    pub origin: Origin,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub tint: Option<Color>,
+}
+
+impl Default for DrawParams {
+    fn default() -> Self {
+        Self {
+            x: 0.,
+            y: 0.,
+            rotation: 0.,
+            scale_x: 1.,
+            scale_y: 1.,
+            origin: Origin::Pixels(0., 0.),
+            flip_x: false,
+            flip_y: false,
+            tint: None,
+        }
+    }
+}
+
+impl DrawParams {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self {
+            x,
+            y,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_position(mut self, x: f32, y: f32) -> Self {
+        self.x = x;
+        self.y = y;
+        self
+    }
+
+    pub fn with_rotation(mut self, radians: f32) -> Self {
+        self.rotation = radians;
+        self
+    }
+
+    pub fn with_rotation_degrees(mut self, degrees: f32) -> Self {
+        self.rotation = degrees.to_radians();
+        self
+    }
+
+    /*
+     * with_scale()
+     *
+     * @brief : A negative scale flips the image, like flip_x / flip_y
+     */
+    pub fn with_scale(mut self, scale_x: f32, scale_y: f32) -> Self {
+        self.scale_x = scale_x;
+        self.scale_y = scale_y;
+        self
+    }
+
+    pub fn with_origin(mut self, x: f32, y: f32) -> Self {
+        self.origin = Origin::Pixels(x, y);
+        self
+    }
+
+    pub fn with_origin_normalized(mut self, x: f32, y: f32) -> Self {
+        self.origin = Origin::Normalized(x, y);
+        self
+    }
+
+    pub fn with_flip(mut self, flip_x: bool, flip_y: bool) -> Self {
+        self.flip_x = flip_x;
+        self.flip_y = flip_y;
+        self
+    }
+
+    /*
+     * with_tint()
+     *
+     * @brief : Multiply the colors (and alpha) of the image
+     */
+    pub fn with_tint(mut self, color: Color) -> Self {
+        self.tint = Some(color);
+        self
+    }
+
+    // Draw params of the positional arguments of draw_full / print_full
+    fn from_full(x: f32, y: f32, angle: f64, scale_x: f32, scale_y: f32, origin_x: f32, origin_y: f32) -> Self {
+        DrawParams::new(x, y)
+            .with_rotation_degrees(angle as f32)
+            .with_scale(scale_x, scale_y)
+            .with_origin(origin_x, origin_y)
+    }
+}
+
+/*
+ * DrawState : Settings of graphics used by a draw call, recorded with the
+ *             call in deferred mode
+ */
+#[derive(Clone)]
+pub(crate) struct DrawState {
+    color: Color,
+    blend_mode: BlendMode,
+    line_width: f32,
+    line_style: LineStyle,
+    line_dash: Vec<f32>,
+    sx: f32,
+    sy: f32,
+    font: Option<Font>,
+}
+
 pub struct Graphics {
     // Général
     pub(crate) canvas: Canvas<sdl2::video::Window>,
 
     // Fonts
-    pub(crate) _new_fonts: Vec<Font>,
+    ttf_context: Gc2dResult<&'static Sdl2TtfContext>,
+    fonts: FontsManager<'static, 'static>,
     pub(crate) actual_font: Option<Font>,
+    text_cache: TextureCache<TextKey>,
+    gradient_cache: TextureCache<GradientKey>,
+    texts: TextsManager,
 
     // Images
     images: ImagesManager,
+    // Image drawn into instead of the window
+    target: Option<ImageHandle>,
+    // White disk scaled and tinted for small circles, ex : particles
+    disk: Option<Image>,
 
     // Color
     actual_color: Color,
     background_color: Color,
     default_color: Color,
 
+    // Blending
+    blend_mode: BlendMode,
+
+    // Lines
+    line_width: f32,
+    line_style: LineStyle,
+    line_dash: Vec<f32>,
+
+    // Filtering
+    default_filter: FilterMode,
+    pixel_perfect: bool,
+
     // Transformations
     actual_sx: f32,
     actual_sy: f32,
+
+    // Deferred drawing
+    queue: DrawQueue,
+
+    transition: Option<Transition>,
 }
 
 impl Graphics {
@@ -65,12 +252,26 @@ impl Graphics {
             actual_color: Color::WHITE,
             default_color: Color::WHITE,
             background_color: Color::BLACK,
+            blend_mode: BlendMode::Alpha,
+            line_width: 1.,
+            line_style: LineStyle::Rough,
+            line_dash: Vec::new(),
+            default_filter: FilterMode::Linear,
+            pixel_perfect: false,
             actual_font: None,
             actual_sx: 1.,
             actual_sy: 1.,
-
-            _new_fonts: Vec::new(),
+            queue: DrawQueue::new(),
+            transition: None,
+
+            ttf_context: ctx.get_ttf_context(),
+            fonts: FontsManager::new(),
+            text_cache: TextureCache::new(text::TEXT_CACHE_SIZE),
+            gradient_cache: TextureCache::new(gradient::GRADIENT_CACHE_SIZE),
+            texts: TextsManager::new(),
+            disk: None,
             images,
+            target: None,
         }
     }
 
@@ -102,6 +303,10 @@ impl Graphics {
      * @brief : Prepare to drawing, call before drawing
      **********************************************************/
     pub(crate) fn begin_draw(&mut self) {
+        self.images.next_frame();
+        self.text_cache.next_frame();
+        self.gradient_cache.next_frame();
+        self.texts.next_frame();
         self.set_color(self.background_color);
         self.canvas.clear();
         self.apply_default_color();
@@ -112,8 +317,14 @@ impl Graphics {
      *
      * @brief : Call after drawing
      **********************************************************/
-     pub(crate) fn end_draw(&mut self) {
+     pub(crate) fn end_draw(&mut self) -> Gc2dResult<()> {
+        // A canvas left as target would hide the frame
+        if self.target.is_some() {
+            let _ = self.set_canvas(None);
+        }
+        let result = self.flush().and(self.draw_transition());
         self.canvas.present();
+        result
     }
 
     //=======================================================================
@@ -136,6 +347,309 @@ impl Graphics {
         self.background_color = color;
     }
 
+    //=======================================================================
+    //                             BLENDING
+    //=======================================================================
+    /***********************************************************
+     * set_blend_mode()
+     *
+     * @brief : Blend mode used by the next primitives and images
+     */
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.blend_mode = blend_mode;
+        self.canvas.set_blend_mode(blend_mode.to_sdl_blend_mode());
+    }
+
+    pub fn get_blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+
+    //=======================================================================
+    //                             FILTERING
+    //=======================================================================
+    /***********************************************************
+     * set_default_filter()
+     *
+     * @brief : Filter of the images without their own filter
+     */
+    pub fn set_default_filter(&mut self, filter: FilterMode) {
+        self.default_filter = filter;
+    }
+
+    pub fn get_default_filter(&self) -> FilterMode {
+        self.default_filter
+    }
+
+    /***********************************************************
+     * set_image_filter()
+     *
+     * @brief : Filter of an image, None to use the default filter
+     */
+    pub fn set_image_filter(&mut self, image: ImageHandle, filter: Option<FilterMode>) -> Gc2dResult<()> {
+        self.images.get_image_mut(image)?.filter = filter;
+        Ok(())
+    }
+
+    /***********************************************************
+     * set_pixel_perfect()
+     *
+     * @brief : Snap the images to whole screen pixels after scaling,
+     *          otherwise they are placed with sub-pixel precision
+     */
+    pub fn set_pixel_perfect(&mut self, pixel_perfect: bool) {
+        self.pixel_perfect = pixel_perfect;
+    }
+
+    pub fn is_pixel_perfect(&self) -> bool {
+        self.pixel_perfect
+    }
+
+    // Blend mode and filter of graphics on the texture of an image, before a copy
+    fn prepare_image(image: &mut Image, blend_mode: BlendMode, default_filter: FilterMode) {
+        image.texture.set_blend_mode(blend_mode.to_sdl_blend_mode());
+        image.set_texture_filter(default_filter);
+    }
+
+    //=======================================================================
+    //                             DEFERRED DRAWING
+    //=======================================================================
+    /***********************************************************
+     * set_deferred()
+     *
+     * @brief : In deferred mode the draw calls are recorded with the actual
+     *          layer and depth, and drawn sorted at the end of the frame.
+     *          Draws into a canvas are never deferred.
+     */
+    pub fn set_deferred(&mut self, deferred: bool) {
+        self.queue.enabled = deferred;
+    }
+
+    pub fn is_deferred(&self) -> bool {
+        self.queue.enabled
+    }
+
+    /***********************************************************
+     * new_layer()
+     *
+     * @brief : Add a layer (or update it), drawn by increasing order. A layer
+     *          without transform ignores set_scale(), ex : the UI.
+     *          The layer "default" (order 0) exists from the start.
+     */
+    pub fn new_layer(&mut self, name: &str, order: i32, transform: bool) {
+        self.queue.new_layer(name, order, transform);
+    }
+
+    /***********************************************************
+     * set_layer()
+     *
+     * @brief : Layer of the next draw calls
+     */
+    pub fn set_layer(&mut self, name: &str) -> Gc2dResult<()> {
+        self.queue.set_layer(name)
+    }
+
+    pub fn get_layer(&self) -> &Layer {
+        self.queue.get_layer()
+    }
+
+    pub fn get_layers(&self) -> &[Layer] {
+        self.queue.get_layers()
+    }
+
+    /***********************************************************
+     * set_depth()
+     *
+     * @brief : Depth of the next draw calls in their layer, the lowest
+     *          is drawn first (ex : the y of a character)
+     */
+    pub fn set_depth(&mut self, depth: f32) {
+        self.queue.set_depth(depth);
+    }
+
+    pub fn get_depth(&self) -> f32 {
+        self.queue.get_depth()
+    }
+
+    fn save_state(&self) -> DrawState {
+        DrawState {
+            color: self.actual_color,
+            blend_mode: self.blend_mode,
+            line_width: self.line_width,
+            line_style: self.line_style,
+            line_dash: self.line_dash.clone(),
+            sx: self.actual_sx,
+            sy: self.actual_sy,
+            font: self.actual_font.clone(),
+        }
+    }
+
+    fn restore_state(&mut self, state: DrawState) {
+        self.set_color(state.color);
+        self.set_blend_mode(state.blend_mode);
+        self.line_width = state.line_width;
+        self.line_style = state.line_style;
+        self.line_dash = state.line_dash;
+        self.actual_sx = state.sx;
+        self.actual_sy = state.sy;
+        self.actual_font = state.font;
+    }
+
+    // True when a draw call has to be recorded instead of drawn
+    fn is_deferring(&self) -> bool {
+        self.queue.enabled && self.target.is_none()
+    }
+
+    fn defer<F: FnOnce(&mut Graphics) -> Gc2dResult<()> + 'static>(&mut self, command: F) {
+        let state = self.save_state();
+        self.queue.push(state, Box::new(command));
+    }
+
+    // Draw the recorded calls, the settings of graphics are kept
+    fn flush(&mut self) -> Gc2dResult<()> {
+        if self.queue.len() == 0 {
+            return Ok(());
+        }
+
+        let saved = self.save_state();
+        let enabled = self.queue.enabled;
+        self.queue.enabled = false;
+
+        let mut first_error = None;
+        for (transform, state, command) in self.queue.take_sorted() {
+            self.restore_state(state);
+            if !transform {
+                self.actual_sx = 1.;
+                self.actual_sy = 1.;
+            }
+            if let Err(e) = command(self) {
+                first_error.get_or_insert(e);
+            }
+        }
+
+        self.restore_state(saved);
+        self.queue.enabled = enabled;
+
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    //=======================================================================
+    //                             TRANSITIONS
+    //=======================================================================
+    /***********************************************************
+     * start_transition()
+     *
+     * @brief : Start a screen transition, over the frames drawn until it
+     *          ends (a transition running is replaced)
+     */
+    pub fn start_transition(&mut self, transition: Transition) {
+        self.stop_transition();
+        self.transition = Some(transition);
+    }
+
+    pub fn stop_transition(&mut self) {
+        if let Some(capture) = self.transition.take().and_then(|t| t.capture) {
+            destroy_texture(capture);
+        }
+    }
+
+    pub fn is_transitioning(&self) -> bool {
+        self.transition.is_some()
+    }
+
+    pub fn get_transition(&self) -> Option<&Transition> {
+        self.transition.as_ref()
+    }
+
+    /***********************************************************
+     * update_transition()
+     *
+     * @brief : Advance the transition, before the update of the game
+     */
+    pub(crate) fn update_transition(&mut self, dt: f32) {
+        let over = match self.transition.as_mut() {
+            Some(transition) => transition.update(dt),
+            None => false,
+        };
+        if over {
+            self.stop_transition();
+        }
+    }
+
+    // Draw the transition over the frame
+    fn draw_transition(&mut self) -> Gc2dResult<()> {
+        let transition = match self.transition.as_mut() {
+            Some(transition) => transition,
+            None => return Ok(()),
+        };
+
+        let (width, height) = self.canvas.output_size()?;
+
+        // First frame of a crossfade : keep it
+        if transition.needs_capture() {
+            let pixels = self.canvas.read_pixels(None, sdl2::pixels::PixelFormatEnum::RGBA32)?;
+            transition.capture = Some(self.images.create_texture(width, height, &pixels)?);
+            return Ok(());
+        }
+
+        let coverage = transition.coverage();
+        let (w, h) = (width as f32, height as f32);
+        let full = |x: f32, y: f32, rw: f32, rh: f32| {
+            sdl2::rect::Rect::new(x.round() as i32, y.round() as i32, rw.round().max(0.) as u32, rh.round().max(0.) as u32)
+        };
+        let second_half = transition.get_progress() > 0.5;
+
+        let (color, rects) = match transition.kind {
+            TransitionKind::Crossfade => {
+                if let Some(capture) = transition.capture.as_mut() {
+                    capture.set_blend_mode(sdl2::render::BlendMode::Blend);
+                    capture.set_alpha_mod((coverage * 255.).round() as u8);
+                    self.canvas.copy(capture, None, None)?;
+                }
+                return Ok(());
+            },
+            TransitionKind::Fade(color) => {
+                let alpha = (color.a as f32 * coverage).round() as u8;
+                (color.with_alpha(alpha), vec![full(0., 0., w, h)])
+            },
+            TransitionKind::WipeHorizontal(color) => {
+                let x = if second_half { w * (1. - coverage) } else { 0. };
+                (color, vec![full(x, 0., w * coverage, h)])
+            },
+            TransitionKind::WipeVertical(color) => {
+                let y = if second_half { h * (1. - coverage) } else { 0. };
+                (color, vec![full(0., y, w, h * coverage)])
+            },
+            TransitionKind::Iris(color) => {
+                // Everything out of a circle on the center
+                let (cx, cy) = (w / 2., h / 2.);
+                let radius = (1. - coverage) * (cx * cx + cy * cy).sqrt();
+                let mut rects = Vec::new();
+                for y in 0..height {
+                    let dy = y as f32 + 0.5 - cy;
+                    if dy.abs() >= radius {
+                        rects.push(full(0., y as f32, w, 1.));
+                    } else {
+                        let half = (radius * radius - dy * dy).sqrt();
+                        rects.push(full(0., y as f32, cx - half, 1.));
+                        rects.push(full(cx + half, y as f32, w - cx - half, 1.));
+                    }
+                }
+                (color, rects)
+            },
+        };
+
+        let rects: Vec<sdl2::rect::Rect> = rects.into_iter().filter(|r| r.width() > 0 && r.height() > 0).collect();
+        self.canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+        self.canvas.set_draw_color(color.to_sdl_color());
+        let result = self.canvas.fill_rects(&rects);
+        self.canvas.set_draw_color(self.actual_color.to_sdl_color());
+        self.canvas.set_blend_mode(self.blend_mode.to_sdl_blend_mode());
+        result
+    }
+
     //=======================================================================
     //                             PRIMITIVES
     //=======================================================================
@@ -144,7 +658,12 @@ impl Graphics {
      *
      * @brief : Draw a line
      */
-    pub fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, color: Option<Color>) {
+    pub fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, color: Option<Color>) -> Gc2dResult<()> {
+
+        if self.is_deferring() {
+            self.defer(move |g| g.line(x1, y1, x2, y2, color));
+            return Ok(());
+        }
 
         let actual_color = self.actual_color;
 
@@ -152,16 +671,19 @@ impl Graphics {
             self.set_color(color);
         }
 
-        self.canvas
-            .draw_line(
+        let result = if self.is_plain_line() {
+            self.canvas.draw_line(
                 sdl2::rect::Point::new(x1 as i32, y1 as i32),
                 sdl2::rect::Point::new(x2 as i32, y2 as i32),
             )
-            .unwrap();
-        
-        if let Some(_) = color {
+        } else {
+            self.stroke(&[(x1, y1), (x2, y2)], false)
+        };
+
+        if color.is_some() {
             self.set_color(actual_color);
         }
+        result
     }
 
     /***********************************************************
@@ -169,7 +691,12 @@ impl Graphics {
      *
      * @brief : Draw a rectangle
      */
-    pub fn rectangle(&mut self, mode: DrawMode, x: f32, y: f32, width: f32, height: f32, color: Option<Color>) {
+    pub fn rectangle(&mut self, mode: DrawMode, x: f32, y: f32, width: f32, height: f32, color: Option<Color>) -> Gc2dResult<()> {
+
+        if self.is_deferring() {
+            self.defer(move |g| g.rectangle(mode, x, y, width, height, color));
+            return Ok(());
+        }
 
         let actual_color = self.actual_color;
 
@@ -177,23 +704,23 @@ impl Graphics {
             self.set_color(color);
         }
 
-        match mode {
+        let result = match mode {
             DrawMode::Fill => {
-                self.canvas
-                    .fill_rect(sdl2::rect::Rect::new( x as i32, y as i32, width as u32, height as u32)) 
-                    .unwrap();
-        
+                self.canvas.fill_rect(sdl2::rect::Rect::new( x as i32, y as i32, width as u32, height as u32))
+            },
+            DrawMode::Line if self.is_plain_line() => {
+                self.canvas.draw_rect(sdl2::rect::Rect::new( x as i32, y as i32, width as u32, height as u32))
             },
             DrawMode::Line => {
-                self.canvas
-                    .draw_rect(sdl2::rect::Rect::new( x as i32, y as i32, width as u32, height as u32)) 
-                    .unwrap();
+                let corners = [(x, y), (x + width, y), (x + width, y + height), (x, y + height)];
+                self.stroke(&corners, true)
             },
-        }
+        };
 
-        if let Some(_) = color {
+        if color.is_some() {
             self.set_color(actual_color);
         }
+        result
     }
 
     /***********************************************************
@@ -201,7 +728,12 @@ impl Graphics {
      *
      * @brief : Draw a circle
      */
-    pub fn circle(&mut self, mode: DrawMode, x_center: f32, y_center: f32, radius: f32, color: Option<Color>) {
+    pub fn circle(&mut self, mode: DrawMode, x_center: f32, y_center: f32, radius: f32, color: Option<Color>) -> Gc2dResult<()> {
+
+        if self.is_deferring() {
+            self.defer(move |g| g.circle(mode, x_center, y_center, radius, color));
+            return Ok(());
+        }
 
         let actual_color = self.actual_color;
 
@@ -209,187 +741,1202 @@ impl Graphics {
             self.set_color(color);
         }
 
+        let result = if matches!(mode, DrawMode::Line) && !self.is_plain_line() {
+            self.stroke(&path::circle_polygon(x_center, y_center, radius), true)
+        } else {
+            self.circle_points(mode, x_center, y_center, radius)
+        };
+
+        if color.is_some() {
+            self.set_color(actual_color);
+        }
+        result
+    }
+
+    // Pixels of a circle, all of them (DrawMode::Fill) or those of its border
+    fn circle_points(&mut self, mode: DrawMode, x_center: f32, y_center: f32, radius: f32) -> Gc2dResult<()> {
         for x in (x_center - radius) as i32..=(x_center + radius) as i32 {
             for y in (y_center - radius) as i32..=(y_center + radius) as i32 {
 
                 let distance = Graphics::distance_euclidienne(x_center, y_center, x as f32, y as f32).floor();
 
-                if distance <= radius {
-                    if match mode { DrawMode::Fill => true, _ => false } || distance == radius {
-                        self.canvas
-                        .draw_point(sdl2::rect::Point::new(x, y))
-                        .unwrap()
-                    }
+                if distance <= radius && (matches!(mode, DrawMode::Fill) || distance == radius) {
+                    self.canvas.draw_point(sdl2::rect::Point::new(x, y))?;
                 }
 
             }
         }
-
-        if let Some(_) = color {
-            self.set_color(actual_color);
-        }
-    }
-
-
-    //=======================================================================
-    //                             SCALE
-    //=======================================================================
-    pub fn set_scale(&mut self, sx: f32, sy: f32) {
-        self.actual_sx = sx;
-        self.actual_sy = sy;
+        Ok(())
     }
 
-    pub fn get_scale(&self) -> (f32, f32) {
-        (self.actual_sx, self.actual_sy)
-    }
 
-    //=======================================================================
-    //                             IMAGES
-    //=======================================================================
-    pub fn new_image(&mut self, filename: &str) -> Gc2dResult<()> {
-        self.images.new_image(filename)
-    }
+    /***********************************************************
+     * disk()
+     *
+     * @brief : Fill a circle with one scaled texture instead of its pixels,
+     *          faster for many small circles (the edge is not exact)
+     */
+    pub(crate) fn disk(&mut self, x_center: f32, y_center: f32, radius: f32, color: Color) -> Gc2dResult<()> {
+        const DISK_SIZE: u32 = 64;
+
+        let mut disk = match self.disk.take() {
+            Some(disk) => disk,
+            None => {
+                let half = DISK_SIZE as f32 / 2.;
+                let mut data = ImageData::new(DISK_SIZE, DISK_SIZE);
+                data.map_pixels(|x, y, _| {
+                    if Graphics::distance_euclidienne(x as f32 + 0.5, y as f32 + 0.5, half, half) <= half {
+                        Color::WHITE
+                    } else {
+                        Color::TRANSPARENT
+                    }
+                });
+                Image::from_texture(self.images.create_texture(DISK_SIZE, DISK_SIZE, data.get_pixels())?)
+            },
+        };
 
-    pub fn draw(&mut self, filename: &str, quad: Option<Quad>, x: f32, y: f32, angle: f64) {
-        self.draw_image(ImageType::ImageFromFile(String::from(filename), quad), x, y, angle, 1f32, 1f32, 0f32, 0f32);
+        let scale = radius * 2. / DISK_SIZE as f32;
+        let params = DrawParams::new(x_center, y_center)
+            .with_origin_normalized(0.5, 0.5)
+            .with_scale(scale, scale)
+            .with_tint(color);
+        let result = self.draw_image(ImageType::FromTexture(&mut disk), &params);
+        self.disk = Some(disk);
+        result
     }
 
-    pub fn draw_full(&mut self, filename: &str, quad: Option<Quad>, x: f32, y: f32, angle: f64, scale_x: f32, scale_y: f32, origin_x: f32, origin_y: f32) {
-        self.draw_image(ImageType::ImageFromFile(String::from(filename), quad), x, y, angle, scale_x, scale_y, origin_x, origin_y);
-    }
+    /***********************************************************
+     * polyline()
+     *
+     * @brief : Draw connected lines through the points, with the line settings
+     */
+    pub fn polyline(&mut self, points: &[(f32, f32)], color: Option<Color>) -> Gc2dResult<()> {
 
-    fn draw_image(&mut self, image_type: ImageType, x: f32, y: f32, angle: f64, scale_x: f32, scale_y: f32, origin_x: f32, origin_y: f32) {
+        if self.is_deferring() {
+            let points = points.to_vec();
+            self.defer(move |g| g.polyline(&points, color));
+            return Ok(());
+        }
 
-        let (image, quad) = match image_type {
-            ImageType::FromTexture(i) => (Some(i), None),
-            ImageType::ImageFromFile(f, quad) => (self.images.get_image(f.as_str()), quad),
-        };
+        let actual_color = self.actual_color;
 
-        if image.is_none() {
-            return;
+        if let Some(color) = color {
+            self.set_color(color);
         }
 
-        let image = image.unwrap();
-
-        let scalex = scale_x * self.actual_sx;
-        let scaley = scale_y * self.actual_sy;
+        let result = self.stroke(points, false);
 
-        let mut dst = sdl2::rect::Rect::new((x * self.actual_sx)as i32,(y * self.actual_sy) as i32, image.width as u32, image.height as u32);
-        dst.h = ((dst.h as f32) * scalex) as i32;
-        dst.w = ((dst.w as f32) * scaley) as i32;
+        if color.is_some() {
+            self.set_color(actual_color);
+        }
+        result
+    }
 
-        let mut src: Option<sdl2::rect::Rect> = Option::None;
+    /***********************************************************
+     * polygon()
+     *
+     * @brief : Draw a polygon of at least 3 points
+     */
+    pub fn polygon(&mut self, mode: DrawMode, points: &[(f32, f32)], color: Option<Color>) -> Gc2dResult<()> {
 
-        if let Some(q) = quad {
-            let rect = sdl2::rect::Rect::new((q.x * self.actual_sx) as i32, (q.y * self.actual_sy) as i32 , q.width as u32, q.height as u32);
-            src = Some(rect);
-            dst.h = ((rect.h as f32) * scalex) as i32;
-            dst.w = ((rect.w as f32) * scaley) as i32;
+        if self.is_deferring() {
+            let points = points.to_vec();
+            self.defer(move |g| g.polygon(mode, &points, color));
+            return Ok(());
         }
 
-        let mut w_center = Option::None;
-        if origin_x!=0. && origin_y!=0. {
-            w_center = Some(sdl2::rect::Point::new(origin_x as i32, origin_y as i32));
+        if points.len() < 3 {
+            return Err(format!("A polygon needs at least 3 points, got {}", points.len()));
         }
 
-            let flip_h = 
-                if scalex < 0. {
-                    true
-                } 
-                else { 
-                    false 
-                };
+        match mode {
+            // Filled with rectangles, like paths, so that the blend mode applies
+            DrawMode::Fill => {
+                let spans = path::fill_spans(&[points.to_vec()], self.canvas.output_size()?);
+                self.draw_spans(&spans, color)
+            },
+            DrawMode::Line => {
+                let actual_color = self.actual_color;
+
+                if let Some(color) = color {
+                    self.set_color(color);
+                }
 
-            let flip_v = 
-                if scaley < 0. {
-                    true
-                } 
-                else { 
-                    false 
-                };
+                let result = self.stroke(points, true);
 
-            self.canvas
-                .copy_ex(
-                    &image.texture, 
-                    src, 
-                    dst, 
-                    angle, 
-                    w_center, 
-                    flip_h,
-                    flip_v, 
-                )
-                .unwrap();
+                if color.is_some() {
+                    self.set_color(actual_color);
+                }
+                result
+            },
+        }
     }
-    
-    /*
-     * get_image_height()
+
+    //=======================================================================
+    //                             LINES
+    //=======================================================================
+    /***********************************************************
+     * set_line_width()
+     *
+     * @brief : Width in pixels of lines, polylines, and polygons, rectangles
+     *          and circles drawn with DrawMode::Line (0. : nothing is drawn)
      */
-    pub fn get_image_height(&self, filename: &str) -> f32 {
-        self.images.get_image_height(filename)
+    pub fn set_line_width(&mut self, width: f32) {
+        self.line_width = width.max(0.);
     }
 
-    /*
+    pub fn get_line_width(&self) -> f32 {
+        self.line_width
+    }
+
+    /***********************************************************
+     * set_line_style()
+     *
+     * @brief : Smooth anti-aliases the edges of the lines (it looks best
+     *          with opaque colors)
+     */
+    pub fn set_line_style(&mut self, style: LineStyle) {
+        self.line_style = style;
+    }
+
+    pub fn get_line_style(&self) -> LineStyle {
+        self.line_style
+    }
+
+    /***********************************************************
+     * set_line_dash()
+     *
+     * @brief : Lengths in pixels of dash, gap, dash, gap... (empty : solid lines)
+     */
+    pub fn set_line_dash(&mut self, pattern: &[f32]) {
+        self.line_dash = pattern.to_vec();
+    }
+
+    pub fn get_line_dash(&self) -> &[f32] {
+        &self.line_dash
+    }
+
+    // Solid, rough and one pixel wide : SDL draws it
+    fn is_plain_line(&self) -> bool {
+        self.line_width == 1. && self.line_style == LineStyle::Rough && self.line_dash.is_empty()
+    }
+
+    // Draw a polyline with the line settings and the actual color
+    fn stroke(&mut self, points: &[(f32, f32)], closed: bool) -> Gc2dResult<()> {
+
+        if self.line_width <= 0. {
+            return Ok(());
+        }
+
+        // aa_line() changes the draw color and the blend mode of the renderer
+        let (draw_color, blend_mode) = (self.canvas.draw_color(), self.canvas.blend_mode());
+        let result = self.stroke_dashes(points, closed);
+        self.canvas.set_draw_color(draw_color);
+        self.canvas.set_blend_mode(blend_mode);
+        result
+    }
+
+    fn stroke_dashes(&mut self, points: &[(f32, f32)], closed: bool) -> Gc2dResult<()> {
+
+        let color = self.actual_color.to_sdl_color();
+        let dashes = path::dash(points, closed, &self.line_dash);
+        // A solid closed polyline is joined at its start too
+        let closed = closed && points.len() > 2 && dashes.len() == 1 && self.line_dash.is_empty();
+
+        for dash in dashes.iter() {
+            match (self.line_width <= 1., self.line_style) {
+                (true, LineStyle::Rough) => {
+                    let points: Vec<sdl2::rect::Point> = dash
+                        .iter()
+                        .map(|(x, y)| sdl2::rect::Point::new(x.round() as i32, y.round() as i32))
+                        .collect();
+                    self.canvas.draw_lines(points.as_slice())?;
+                },
+                (true, LineStyle::Smooth) => {
+                    for w in dash.windows(2) {
+                        let (a, b) = (w[0], w[1]);
+                        self.canvas.aa_line(a.0.round() as i16, a.1.round() as i16, b.0.round() as i16, b.1.round() as i16, color)?;
+                    }
+                },
+                (false, style) => {
+                    // The last point of a closed dash repeats the first one
+                    let dash = if closed { &dash[..dash.len() - 1] } else { &dash[..] };
+                    let polygons = path::stroke_polygons(dash, closed, self.line_width, LineJoin::Miter, LineCap::Butt);
+                    let spans = path::union_spans(&polygons, self.canvas.output_size()?);
+                    self.draw_spans(&spans, None)?;
+
+                    if style == LineStyle::Smooth {
+                        let mut edges = dash.to_vec();
+                        if closed {
+                            edges.push(dash[0]);
+                        }
+                        for (a, b) in path::stroke_edges(&edges, self.line_width) {
+                            self.canvas.aa_line(a.0.round() as i16, a.1.round() as i16, b.0.round() as i16, b.1.round() as i16, color)?;
+                        }
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+
+    //=======================================================================
+    //                             PATHS
+    //=======================================================================
+    /***********************************************************
+     * path()
+     *
+     * @brief : Draw a path, stroked with its line width, joins and caps
+     *          (DrawMode::Line) or filled (DrawMode::Fill, a part inside
+     *          another one makes a hole)
+     */
+    pub fn path(&mut self, mode: DrawMode, path: &Path, color: Option<Color>) -> Gc2dResult<()> {
+
+        if self.is_deferring() {
+            let path = path.clone();
+            self.defer(move |g| g.path(mode, &path, color));
+            return Ok(());
+        }
+
+        let subpaths = path.flatten();
+        let clip = self.canvas.output_size()?;
+
+        let spans = match mode {
+            DrawMode::Fill => {
+                let polygons: Vec<Vec<(f32, f32)>> = subpaths
+                    .into_iter()
+                    .filter(|subpath| subpath.points.len() > 2)
+                    .map(|subpath| subpath.points)
+                    .collect();
+                path::fill_spans(&polygons, clip)
+            },
+            DrawMode::Line => {
+                let mut polygons = Vec::new();
+                for subpath in subpaths.iter() {
+                    polygons.extend(path::stroke_polygons(&subpath.points, subpath.closed, path.line_width, path.line_join, path.line_cap));
+                }
+                path::union_spans(&polygons, clip)
+            },
+        };
+
+        self.draw_spans(&spans, color)
+    }
+
+    // Fill rows of pixels
+    fn draw_spans(&mut self, spans: &[Span], color: Option<Color>) -> Gc2dResult<()> {
+        let rects: Vec<sdl2::rect::Rect> = spans
+            .iter()
+            .map(|(y, x0, x1)| sdl2::rect::Rect::new(*x0, *y, (x1 - x0 + 1) as u32, 1))
+            .collect();
+
+        let actual_color = self.actual_color;
+        if let Some(color) = color {
+            self.set_color(color);
+        }
+
+        let result = self.canvas.fill_rects(&rects);
+
+        if color.is_some() {
+            self.set_color(actual_color);
+        }
+        result
+    }
+
+    //=======================================================================
+    //                             GRADIENTS
+    //=======================================================================
+    /*
+     * The gradient is computed on the CPU, its texture is kept while the same
+     * gradient is drawn with the same shape and size (the position can change).
+     */
+    /***********************************************************
+     * rectangle_gradient()
+     *
+     * @brief : Fill a rectangle with a gradient
+     */
+    pub fn rectangle_gradient(&mut self, x: f32, y: f32, width: f32, height: f32, gradient: &Gradient) -> Gc2dResult<()> {
+        if self.is_deferring() {
+            let gradient = gradient.clone();
+            self.defer(move |g| g.rectangle_gradient(x, y, width, height, &gradient));
+            return Ok(());
+        }
+
+        let (w, h) = gradient::bounding_size(width, height)?;
+        let key = GradientKey { gradient: gradient.clone(), shape: GradientShape::Rectangle, width: w, height: h };
+        self.draw_gradient(x, y, key, || gradient.fill(w, h, |_, _| true))
+    }
+
+    /***********************************************************
+     * circle_gradient()
+     *
+     * @brief : Fill a circle with a gradient, ex : a vignette
+     */
+    pub fn circle_gradient(&mut self, x_center: f32, y_center: f32, radius: f32, gradient: &Gradient) -> Gc2dResult<()> {
+        if self.is_deferring() {
+            let gradient = gradient.clone();
+            self.defer(move |g| g.circle_gradient(x_center, y_center, radius, &gradient));
+            return Ok(());
+        }
+
+        let (w, h) = gradient::bounding_size(radius * 2., radius * 2.)?;
+        let key = GradientKey { gradient: gradient.clone(), shape: GradientShape::Circle, width: w, height: h };
+        self.draw_gradient(x_center - radius, y_center - radius, key, || {
+            gradient.fill(w, h, |px, py| Graphics::distance_euclidienne(px, py, radius, radius) <= radius)
+        })
+    }
+
+    /***********************************************************
+     * polygon_gradient()
+     *
+     * @brief : Fill a polygon with a gradient spread over its bounding box
+     */
+    pub fn polygon_gradient(&mut self, points: &[(f32, f32)], gradient: &Gradient) -> Gc2dResult<()> {
+
+        if self.is_deferring() {
+            let (points, gradient) = (points.to_vec(), gradient.clone());
+            self.defer(move |g| g.polygon_gradient(&points, &gradient));
+            return Ok(());
+        }
+
+        if points.len() < 3 {
+            return Err(format!("A polygon needs at least 3 points, got {}", points.len()));
+        }
+
+        let left = points.iter().map(|p| p.0).fold(f32::MAX, f32::min).floor();
+        let top = points.iter().map(|p| p.1).fold(f32::MAX, f32::min).floor();
+        let right = points.iter().map(|p| p.0).fold(f32::MIN, f32::max);
+        let bottom = points.iter().map(|p| p.1).fold(f32::MIN, f32::max);
+
+        let (w, h) = gradient::bounding_size(right - left, bottom - top)?;
+        let local: Vec<(f32, f32)> = points.iter().map(|(x, y)| (x - left, y - top)).collect();
+        let key = GradientKey { gradient: gradient.clone(), shape: GradientShape::Polygon(local.clone()), width: w, height: h };
+        self.draw_gradient(left, top, key, || gradient.fill(w, h, |px, py| gradient::point_in_polygon(px, py, &local)))
+    }
+
+    // Copy a gradient at (x, y), its texture comes from the cache or from fill()
+    fn draw_gradient<F: FnOnce() -> ImageData>(&mut self, x: f32, y: f32, key: GradientKey, fill: F) -> Gc2dResult<()> {
+
+        if key.width == 0 || key.height == 0 {
+            return Ok(());
+        }
+
+        let mut texture = match self.gradient_cache.take(&key) {
+            Some(texture) => texture,
+            None => {
+                let data = fill();
+                self.images.create_texture(data.get_width(), data.get_height(), data.get_pixels())?
+            },
+        };
+        texture.set_blend_mode(self.blend_mode.to_sdl_blend_mode());
+
+        let dst = sdl2::rect::Rect::new(x.round() as i32, y.round() as i32, key.width, key.height);
+        let result = self.canvas.copy(&texture, None, dst);
+
+        self.gradient_cache.put(key, texture);
+        result
+    }
+
+    //=======================================================================
+    //                             SCALE
+    //=======================================================================
+    pub fn set_scale(&mut self, sx: f32, sy: f32) {
+        self.actual_sx = sx;
+        self.actual_sy = sy;
+    }
+
+    pub fn get_scale(&self) -> (f32, f32) {
+        (self.actual_sx, self.actual_sy)
+    }
+
+    //=======================================================================
+    //                             IMAGES
+    //=======================================================================
+    pub fn new_image(&mut self, filename: &str) -> Gc2dResult<ImageHandle> {
+        self.images.new_image(filename)
+    }
+
+    pub fn new_image_from_bytes(&mut self, name: &str, bytes: &[u8]) -> Gc2dResult<ImageHandle> {
+        self.images.new_image_from_bytes(name, bytes)
+    }
+
+    pub fn new_image_from_rgba(&mut self, width: u32, height: u32, pixels: &[u8]) -> Gc2dResult<ImageHandle> {
+        self.images.new_image_from_rgba(width, height, pixels)
+    }
+
+    pub fn new_image_from_data(&mut self, data: &ImageData) -> Gc2dResult<ImageHandle> {
+        self.images.new_image_from_data(data)
+    }
+
+    pub(crate) fn new_image_decoded(&mut self, filename: &str, data: &ImageData) -> Gc2dResult<ImageHandle> {
+        self.images.new_image_decoded(filename, data)
+    }
+
+    /***********************************************************
+     * unload_image()
+     *
+     * @brief : Free an image, drawing its handle is then an error
+     */
+    pub fn unload_image(&mut self, image: ImageHandle) -> Gc2dResult<()> {
+        if self.target == Some(image) {
+            self.set_canvas(None)?;
+        }
+        self.images.unload_image(image)
+    }
+
+    /***********************************************************
+     * begin_image_group()
+     *
+     * @brief : Images loaded until end_image_group() belong to the group,
+     *          ex : the images of a level
+     */
+    pub fn begin_image_group(&mut self, name: &str) {
+        self.images.begin_group(name);
+    }
+
+    pub fn end_image_group(&mut self) {
+        self.images.end_group();
+    }
+
+    /***********************************************************
+     * unload_image_group()
+     *
+     * @brief : Free the images of a group which are not used elsewhere,
+     *          returns the number of images freed
+     */
+    pub fn unload_image_group(&mut self, name: &str) -> usize {
+        self.images.unload_group(name)
+    }
+
+    /***********************************************************
+     * set_image_budget()
+     *
+     * @brief : Texture memory limit in megabytes (None : no limit). Images
+     *          loaded from files are freed, least recently drawn first, and
+     *          loaded again when they are drawn.
+     */
+    pub fn set_image_budget(&mut self, megabytes: Option<f32>) {
+        self.images.set_budget(megabytes.map(|mb| (mb * 1024. * 1024.) as usize));
+    }
+
+    /***********************************************************
+     * get_image_stats()
+     *
+     * @brief : Number of textures loaded and their estimated memory
+     */
+    pub fn get_image_stats(&self) -> ImageStats {
+        self.images.get_stats()
+    }
+
+    /***********************************************************
+     * get_image_data()
+     *
+     * @brief : Read the pixels of an image back from its texture
+     */
+    pub fn get_image_data(&mut self, image: ImageHandle) -> Gc2dResult<ImageData> {
+        let (width, height) = (self.images.get_image_width(image)? as u32, self.images.get_image_height(image)? as u32);
+        let target = self.images.create_target_texture(width, height)?;
+
+        // Copy the image as it is into a texture the renderer can read
+        let result = (|| {
+            let source = self.images.get_image_mut(image)?;
+            source.texture.set_blend_mode(sdl2::render::BlendMode::None);
+            let texture = &source.texture;
+
+            let previous = self.target;
+            let copied = unsafe { sdl2::sys::SDL_SetRenderTarget(self.canvas.raw(), target.raw()) };
+            if copied != 0 {
+                return Err(sdl2::get_error());
+            }
+            let pixels = self.canvas
+                .copy(texture, None, None)
+                .and_then(|_| self.canvas.read_pixels(None, sdl2::pixels::PixelFormatEnum::RGBA32));
+            self.set_canvas(previous)?;
+
+            ImageData::from_rgba(width, height, pixels?)
+        })();
+
+        destroy_texture(target);
+        result
+    }
+
+    /***********************************************************
+     * palette_swap()
+     *
+     * @brief : New image where the colors of from become the colors of to,
+     *          ex : one sprite for every team color
+     */
+    pub fn palette_swap(&mut self, image: ImageHandle, from: &Palette, to: &Palette) -> Gc2dResult<ImageHandle> {
+        let data = self.get_image_data(image)?;
+        let filter = self.images.get_image_mut(image)?.filter;
+
+        let swapped = self.images.new_image_from_data(&from.swap(&data, to))?;
+        self.set_image_filter(swapped, filter)?;
+        Ok(swapped)
+    }
+
+    pub fn draw(&mut self, image: ImageHandle, quad: Option<Quad>, x: f32, y: f32, angle: f64) -> Gc2dResult<()> {
+        self.draw_with(image, quad, &DrawParams::new(x, y).with_rotation_degrees(angle as f32))
+    }
+
+    pub fn draw_full(&mut self, image: ImageHandle, quad: Option<Quad>, x: f32, y: f32, angle: f64, scale_x: f32, scale_y: f32, origin_x: f32, origin_y: f32) -> Gc2dResult<()> {
+        self.draw_with(image, quad, &DrawParams::from_full(x, y, angle, scale_x, scale_y, origin_x, origin_y))
+    }
+
+    /***********************************************************
+     * draw_with()
+     *
+     * @brief : Draw an image (or a quad of it) with draw params
+     */
+    pub fn draw_with(&mut self, image: ImageHandle, quad: Option<Quad>, params: &DrawParams) -> Gc2dResult<()> {
+        if self.is_deferring() {
+            let params = *params;
+            self.defer(move |g| g.draw_with(image, quad, &params));
+            return Ok(());
+        }
+        self.draw_image(ImageType::FromHandle(image, quad), params)
+    }
+
+    fn draw_image(&mut self, image_type: ImageType, params: &DrawParams) -> Gc2dResult<()> {
+
+        let (image, quad) = match image_type {
+            ImageType::FromTexture(i) => (i, None),
+            ImageType::FromHandle(handle, quad) => (self.images.get_image_mut(handle)?, quad),
+        };
+
+        // Source : the quad is in pixels of the image, it is never scaled
+        let (src, width, height) = match quad {
+            Some(q) => (Some(sdl2::rect::Rect::new(q.x as i32, q.y as i32, q.width as u32, q.height as u32)), q.width, q.height),
+            None => (None, image.width, image.height),
+        };
+
+        let scale_x = params.scale_x.abs() * self.actual_sx;
+        let scale_y = params.scale_y.abs() * self.actual_sy;
+
+        let mut dst_width = width * scale_x;
+        let mut dst_height = height * scale_y;
+
+        let flip_h = params.flip_x != (params.scale_x < 0.);
+        let flip_v = params.flip_y != (params.scale_y < 0.);
+
+        // Origin in screen pixels, mirrored with the image when flipped
+        let (origin_x, origin_y) = match params.origin {
+            Origin::Pixels(x, y) => (x, y),
+            Origin::Normalized(x, y) => (x * width, y * height),
+        };
+        let mut origin_x = origin_x * scale_x;
+        let mut origin_y = origin_y * scale_y;
+        if flip_h {
+            origin_x = dst_width - origin_x;
+        }
+        if flip_v {
+            origin_y = dst_height - origin_y;
+        }
+
+        let mut dst_x = params.x * self.actual_sx - origin_x;
+        let mut dst_y = params.y * self.actual_sy - origin_y;
+
+        if self.pixel_perfect {
+            dst_x = dst_x.round();
+            dst_y = dst_y.round();
+            dst_width = dst_width.round();
+            dst_height = dst_height.round();
+            origin_x = origin_x.round();
+            origin_y = origin_y.round();
+        }
+
+        if dst_width <= 0. || dst_height <= 0. {
+            return Ok(());
+        }
+
+        Graphics::prepare_image(image, self.blend_mode, self.default_filter);
+        if let Some(tint) = params.tint {
+            image.texture.set_color_mod(tint.r, tint.g, tint.b);
+            image.texture.set_alpha_mod(tint.a);
+        }
+
+        let result = Graphics::copy_ex_f(
+            &mut self.canvas,
+            &image.texture,
+            src,
+            (dst_x, dst_y, dst_width, dst_height),
+            params.rotation.to_degrees() as f64,
+            (origin_x, origin_y),
+            (flip_h, flip_v),
+        );
+
+        if params.tint.is_some() {
+            image.texture.set_color_mod(255, 255, 255);
+            image.texture.set_alpha_mod(255);
+        }
+
+        result
+    }
+
+    // Copy with a floating point destination (SDL >= 2.0.10), not wrapped by the sdl2 crate
+    fn copy_ex_f(canvas: &mut Canvas<sdl2::video::Window>, texture: &sdl2::render::Texture, src: Option<sdl2::rect::Rect>, dst: (f32, f32, f32, f32), angle: f64, center: (f32, f32), flip: (bool, bool)) -> Gc2dResult<()> {
+        use sdl2::sys::{SDL_FPoint, SDL_FRect, SDL_RendererFlip};
+
+        let (mut x, mut y, w, h) = dst;
+        let (mut center_x, mut center_y) = center;
+        let mut angle = angle;
+
+        // SDL has no value for both flips : it is a half turn around the center of dst,
+        // followed by the rotation around center, so rotate half a turn more around the
+        // center of dst and move dst to land at the same place
+        let flip = match flip {
+            (false, false) => SDL_RendererFlip::SDL_FLIP_NONE,
+            (true, false) => SDL_RendererFlip::SDL_FLIP_HORIZONTAL,
+            (false, true) => SDL_RendererFlip::SDL_FLIP_VERTICAL,
+            (true, true) => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let (dx, dy) = ((w / 2. - center_x) as f64, (h / 2. - center_y) as f64);
+                x += (-dx + dx * cos - dy * sin) as f32;
+                y += (-dy + dx * sin + dy * cos) as f32;
+                center_x = w / 2.;
+                center_y = h / 2.;
+                angle += 180.;
+                SDL_RendererFlip::SDL_FLIP_NONE
+            },
+        };
+
+        let dst = SDL_FRect { x, y, w, h };
+        let center = SDL_FPoint { x: center_x, y: center_y };
+
+        let result = unsafe {
+            sdl2::sys::SDL_RenderCopyExF(
+                canvas.raw(),
+                texture.raw(),
+                match src {
+                    Some(ref rect) => rect.raw(),
+                    None => std::ptr::null(),
+                },
+                &dst,
+                angle,
+                &center,
+                flip,
+            )
+        };
+
+        if result != 0 {
+            Err(sdl2::get_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /*
+     * draw_region()
+     *
+     * @brief : Draw the quad src of an image in the rectangle dst
+     */
+    pub(crate) fn draw_region(&mut self, image: ImageHandle, src: Quad, dst: Quad, flip_h: bool, flip_v: bool) -> Gc2dResult<()> {
+
+        if self.is_deferring() {
+            self.defer(move |g| g.draw_region(image, src, dst, flip_h, flip_v));
+            return Ok(());
+        }
+
+        let image = self.images.get_image_mut(image)?;
+        Graphics::prepare_image(image, self.blend_mode, self.default_filter);
+
+        let left = (dst.x * self.actual_sx).round();
+        let top = (dst.y * self.actual_sy).round();
+        let right = ((dst.x + dst.width) * self.actual_sx).round();
+        let bottom = ((dst.y + dst.height) * self.actual_sy).round();
+
+        if right <= left || bottom <= top {
+            return Ok(());
+        }
+
+        self.canvas.copy_ex(
+            &image.texture,
+            sdl2::rect::Rect::new(src.x as i32, src.y as i32, src.width as u32, src.height as u32),
+            sdl2::rect::Rect::new(left as i32, top as i32, (right - left) as u32, (bottom - top) as u32),
+            0.,
+            None,
+            flip_h,
+            flip_v,
+        )
+    }
+
+    /*
+     * draw_nine_slice()
+     *
+     * @brief : Draw a nine slice in the rectangle (x, y, width, height)
+     *          If the rectangle is smaller than the borders, the corners shrink
+     */
+    pub fn draw_nine_slice(&mut self, slice: &NineSlice, x: f32, y: f32, width: f32, height: f32) -> Gc2dResult<()> {
+
+        if self.is_deferring() {
+            let slice = *slice;
+            self.defer(move |g| g.draw_nine_slice(&slice, x, y, width, height));
+            return Ok(());
+        }
+
+        let image = self.images.get_image_mut(slice.image)?;
+        Graphics::prepare_image(image, self.blend_mode, self.default_filter);
+
+        let source = slice.quad.unwrap_or(Quad { x: 0., y: 0., width: image.width, height: image.height });
+
+        // Columns and rows : (source position, source size, target position, target size)
+        let columns = Graphics::nine_slice_bands(source.x, source.width, slice.left, slice.right, x, width);
+        let rows = Graphics::nine_slice_bands(source.y, source.height, slice.top, slice.bottom, y, height);
+
+        for (j, row) in rows.iter().enumerate() {
+            for (i, column) in columns.iter().enumerate() {
+
+                if column.1 <= 0. || row.1 <= 0. || column.3 <= 0. || row.3 <= 0. {
+                    continue;
+                }
+
+                // Edges are tiled along their length, the center in both directions
+                let tile_x = slice.mode == NineSliceMode::Tile && i == 1;
+                let tile_y = slice.mode == NineSliceMode::Tile && j == 1;
+
+                let (step_x, step_y) = (
+                    if tile_x { column.1 } else { column.3 },
+                    if tile_y { row.1 } else { row.3 },
+                );
+
+                let mut dy = 0.;
+                while dy < row.3 {
+                    let part_h = step_y.min(row.3 - dy);
+                    let src_h = if tile_y { part_h } else { row.1 };
+
+                    let mut dx = 0.;
+                    while dx < column.3 {
+                        let part_w = step_x.min(column.3 - dx);
+                        let src_w = if tile_x { part_w } else { column.1 };
+
+                        let src = sdl2::rect::Rect::new(column.0 as i32, row.0 as i32, src_w.max(1.) as u32, src_h.max(1.) as u32);
+
+                        // Round both edges so that the parts don't leave gaps
+                        let left = ((column.2 + dx) * self.actual_sx).round();
+                        let top = ((row.2 + dy) * self.actual_sy).round();
+                        let right = ((column.2 + dx + part_w) * self.actual_sx).round();
+                        let bottom = ((row.2 + dy + part_h) * self.actual_sy).round();
+
+                        if right > left && bottom > top {
+                            let dst = sdl2::rect::Rect::new(left as i32, top as i32, (right - left) as u32, (bottom - top) as u32);
+                            self.canvas.copy(&image.texture, src, dst)?;
+                        }
+
+                        dx += step_x;
+                    }
+                    dy += step_y;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Split a source length and a target length in three bands (start border, center, end border)
+    fn nine_slice_bands(src: f32, src_len: f32, start: f32, end: f32, dst: f32, dst_len: f32) -> [(f32, f32, f32, f32); 3] {
+        let shrink = if start + end > dst_len && start + end > 0. { dst_len / (start + end) } else { 1. };
+        let dst_start = start * shrink;
+        let dst_end = end * shrink;
+
+        [
+            (src, start, dst, dst_start),
+            (src + start, src_len - start - end, dst + dst_start, dst_len - dst_start - dst_end),
+            (src + src_len - end, end, dst + dst_len - dst_end, dst_end),
+        ]
+    }
+
+    /*
+     * get_image_height()
+     */
+    pub fn get_image_height(&self, image: ImageHandle) -> Gc2dResult<f32> {
+        self.images.get_image_height(image)
+    }
+
+    /*
      * get_image_width())
      */
-    pub fn get_image_width(&self, filename: &str) -> f32 {
-        self.images.get_image_width(filename)
+    pub fn get_image_width(&self, image: ImageHandle) -> Gc2dResult<f32> {
+        self.images.get_image_width(image)
     }
     //=======================================================================
-    //                             FONTS
+    //                             CANVAS
     //=======================================================================
-    pub fn new_font(&mut self, filename: &str, point_size: u16) -> Font {
-        let font = Font {
-                filename: String::from(filename),
-                point_size
+    /***********************************************************
+     * new_canvas()
+     *
+     * @brief : Create an image of width x height pixels which can be drawn
+     *          into with set_canvas(), it starts transparent
+     */
+    pub fn new_canvas(&mut self, width: u32, height: u32) -> Gc2dResult<ImageHandle> {
+        let canvas = self.images.new_canvas(width, height)?;
+
+        let previous = self.target;
+        self.set_canvas(Some(canvas))?;
+        let color = self.actual_color;
+        self.set_color(Color::TRANSPARENT);
+        self.canvas.clear();
+        self.set_color(color);
+        self.set_canvas(previous)?;
+
+        Ok(canvas)
+    }
+
+    /***********************************************************
+     * set_canvas()
+     *
+     * @brief : Draw into a canvas image (None : draw into the window again)
+     */
+    pub fn set_canvas(&mut self, canvas: Option<ImageHandle>) -> Gc2dResult<()> {
+
+        let texture = match canvas {
+            Some(handle) => self.images.get_image_mut(handle)?.texture.raw(),
+            None => std::ptr::null_mut(),
         };
-        self._new_fonts.push(font.clone());
-        font
+
+        let result = unsafe { sdl2::sys::SDL_SetRenderTarget(self.canvas.raw(), texture) };
+        if result != 0 {
+            return Err(sdl2::get_error());
+        }
+
+        self.target = canvas;
+        Ok(())
+    }
+
+    pub fn get_canvas(&self) -> Option<ImageHandle> {
+        self.target
+    }
+
+    //=======================================================================
+    //                             FONTS
+    //=======================================================================
+    /***********************************************************
+     * new_font()
+     *
+     * @brief : Load a TrueType font at a size, or a BMFont .fnt file
+     *          (point_size is then ignored). The first font loaded becomes
+     *          the font of graphics.
+     */
+    pub fn new_font(&mut self, filename: &str, point_size: u16) -> Gc2dResult<Font> {
+        let font = Font::new(filename, point_size);
+        self.fonts.new_font(self.ttf_context.clone(), font.clone())?;
+        if self.actual_font.is_none() {
+            self.actual_font = Some(font.clone());
+        }
+        Ok(font)
+    }
+
+    /***********************************************************
+     * new_image_font()
+     *
+     * @brief : Font of an image with the glyphs side by side, separated by
+     *          columns of the color of its top left pixel. glyphs are the
+     *          characters of the image from left to right.
+     *          (BMFont .fnt files are loaded by new_font())
+     */
+    pub fn new_image_font(&mut self, filename: &str, glyphs: &str) -> Gc2dResult<Font> {
+        let font = Font::new(filename, 0);
+        self.fonts.new_image_font(font.clone(), glyphs)?;
+        if self.actual_font.is_none() {
+            self.actual_font = Some(font.clone());
+        }
+        Ok(font)
     }
 
     pub fn set_font(&mut self, font: Option<Font>) {
         self.actual_font = font; 
     }
 
-    pub fn get_text_height(&self, text: &str, fonts: &FontsManager, filename: String, point_size: u16) -> Gc2dResult<u32> {
-        fonts.get_font_height(text, &Font {
-            filename: filename.clone(),
-            point_size
-        })
+    pub fn get_font(&self) -> Option<&Font> {
+        self.actual_font.as_ref()
+    }
+
+    /***********************************************************
+     * get_text_height()
+     *
+     * @brief : Height of a text printed with font, or with the font of
+     *          graphics if None
+     */
+    pub fn get_text_height(&mut self, text: &str, font: Option<&Font>) -> Gc2dResult<u32> {
+        let font = font.or(self.actual_font.as_ref()).ok_or_else(|| String::from("No font to measure the text"))?;
+        self.fonts.get_font_height(text, font)
+    }
+
+    pub fn get_text_width(&mut self, text: &str, font: Option<&Font>) -> Gc2dResult<u32> {
+        let font = font.or(self.actual_font.as_ref()).ok_or_else(|| String::from("No font to measure the text"))?;
+        self.fonts.get_font_width(text, font)
+    }
+
+    pub fn print_full(&mut self, text: String, x: f32, y: f32, angle: f64, scale_x: f32, scale_y: f32, origin_x: f32, origin_y: f32, color: Option<Color>) -> Gc2dResult<()> {
+        self.print_with(text, &DrawParams::from_full(x, y, angle, scale_x, scale_y, origin_x, origin_y), color)
+    }
+
+    /***********************************************************
+     * print_with()
+     *
+     * @brief : Print a text with draw params, like an image
+     *          An error without font, or if the text cannot be rendered
+     *          (ex : a font not loaded, a '\0' in the text)
+     */
+    pub fn print_with(&mut self, text: String, params: &DrawParams, color: Option<Color>) -> Gc2dResult<()> {
+        if self.is_deferring() {
+            let params = *params;
+            self.defer(move |g| g.print_with(text, &params, color));
+            return Ok(());
+        }
+
+        let font = self.actual_font.clone().ok_or_else(|| String::from("No font to print the text"))?;
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        let key = TextKey {
+            font,
+            string: text,
+            color: color.unwrap_or(self.actual_color),
+            style: FontStyle::NORMAL,
+        };
+        self.print_cached(key, params)
+    }
+
+    /***********************************************************
+     * print_shadow()
+     *
+     * @brief : Print a text over its shadow, ex : to read it over a busy
+     *          background
+     */
+    pub fn print_shadow(&mut self, text: String, x: f32, y: f32, color: Option<Color>, shadow: TextShadow) -> Gc2dResult<()> {
+        if self.is_deferring() {
+            self.defer(move |g| g.print_shadow(text, x, y, color, shadow));
+            return Ok(());
+        }
+
+        let font = self.actual_font.clone().ok_or_else(|| String::from("No font to print the text"))?;
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        // The outline of the shadow is of the shadow color too
+        let shadow_key = TextKey {
+            font: font.clone().with_outline(font.outline, shadow.color),
+            string: text.clone(),
+            color: shadow.color,
+            style: FontStyle::NORMAL,
+        };
+        self.print_cached(shadow_key, &DrawParams::new(x + shadow.offset_x, y + shadow.offset_y))?;
+
+        let key = TextKey {
+            font,
+            string: text,
+            color: color.unwrap_or(self.actual_color),
+            style: FontStyle::NORMAL,
+        };
+        self.print_cached(key, &DrawParams::new(x, y))
+    }
+
+    // Draw a text with the texture of the cache
+    fn print_cached(&mut self, key: TextKey, params: &DrawParams) -> Gc2dResult<()> {
+        let texture = match self.text_cache.take(&key) {
+            Some(texture) => texture,
+            None => {
+                let font_creator = self.get_fonts_creator();
+                self.fonts.get_texture(&font_creator, &key.font, &key.string, key.color, key.style)?
+            },
+        };
+
+        let mut image = Image::from_texture(texture);
+        let result = self.draw_image(ImageType::FromTexture(&mut image), params);
+        self.text_cache.put(key, image.texture);
+        result
     }
 
-    pub fn get_text_width(&self, text: &str, fonts: &FontsManager, filename: String, point_size: u16) -> Gc2dResult<u32> {
-        fonts.get_font_width(text, &Font {
-            filename: filename.clone(),
-            point_size
+    /***********************************************************
+     * get_wrapped_text()
+     *
+     * @brief : Lines of a text printed by printf() with the font of graphics,
+     *          and their total height, ex : to size a dialog box
+     */
+    pub fn get_wrapped_text(&mut self, text: &str, wrap_width: f32) -> Gc2dResult<WrappedText> {
+        let font = self.actual_font.as_ref().ok_or_else(|| String::from("No font to measure the text"))?;
+
+        let lines = text::wrap(text, wrap_width, |s| {
+            if s.is_empty() { Ok(0.) } else { self.fonts.get_font_width(s, font).map(|w| w as f32) }
+        })?;
+        let line_height = self.fonts.get_line_height(font)? as f32;
+
+        Ok(WrappedText {
+            height: line_height * lines.len() as f32,
+            lines,
+            line_height,
         })
     }
 
-    pub fn print_full(&mut self, text: String, x: f32, y: f32, angle: f64, scale_x: f32, scale_y: f32, origin_x: f32, origin_y: f32, color: Option<Color>, fonts: &mut FontsManager) {
-        // Only if font is set
-        if let Some(font) = &self.actual_font {
-            if !text.is_empty() {
-                // Witch color ?
-                let l_color = if let Some(color) = color {
-                                        color 
-                                    } else {
-                                        self.actual_color
-                                    };
-
-            // Create an texture from Text
-            let font_creator = self.get_fonts_creator();
-            let texture = fonts.get_texture(&font_creator, &font, text, &l_color); 
-            
-            // Create an image from Texture
-            let image = Image::from_texture(texture.unwrap());
-
-            // Draw text
-            self.draw_image(ImageType::FromTexture(&image), x, y, angle, scale_x, scale_y, origin_x, origin_y);
+    /***********************************************************
+     * printf()
+     *
+     * @brief : Print a text wrapped at wrap_width pixels (and at its newlines),
+     *          aligned between x and x + wrap_width
+     */
+    pub fn printf(&mut self, text: &str, x: f32, y: f32, wrap_width: f32, align: TextAlign, color: Option<Color>) -> Gc2dResult<()> {
+        let wrapped = self.get_wrapped_text(text, wrap_width)?;
+
+        for (i, line) in wrapped.lines.iter().enumerate() {
+            let line_y = y + i as f32 * wrapped.line_height;
+
+            match align {
+                TextAlign::Justify if !line.ends_paragraph => {
+                    // Words placed so that the line fills the width
+                    let font = self.actual_font.clone().ok_or_else(|| String::from("No font to print the text"))?;
+                    let words: Vec<&str> = line.text.split(' ').collect();
+                    let widths = words
+                        .iter()
+                        .map(|w| self.fonts.get_font_width(w, &font).map(|w| w as f32))
+                        .collect::<Gc2dResult<Vec<f32>>>()?;
+                    let gap = if words.len() > 1 {
+                        (wrap_width - widths.iter().sum::<f32>()) / (words.len() - 1) as f32
+                    } else {
+                        0.
+                    };
+
+                    let mut word_x = x;
+                    for (word, width) in words.iter().zip(widths) {
+                        self.print_with(word.to_string(), &DrawParams::new(word_x, line_y), color)?;
+                        word_x += width + gap;
+                    }
+                },
+                _ => {
+                    let line_x = match align {
+                        TextAlign::Center => x + (wrap_width - line.width) / 2.,
+                        TextAlign::Right => x + wrap_width - line.width,
+                        _ => x,
+                    };
+                    self.print_with(line.text.clone(), &DrawParams::new(line_x, line_y), color)?;
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /***********************************************************
+     * get_rich_text_size()
+     *
+     * @brief : (width, height) of a rich text drawn with the font of graphics
+     */
+    pub fn get_rich_text_size(&mut self, text: &RichText, wrap_width: Option<f32>) -> Gc2dResult<(f32, f32)> {
+        let layout = self.layout_rich_text(text, wrap_width)?;
+        Ok((layout.width, layout.height))
+    }
+
+    fn layout_rich_text(&mut self, text: &RichText, wrap_width: Option<f32>) -> Gc2dResult<RichLayout> {
+        let font = self.actual_font.as_ref().ok_or_else(|| String::from("No font to measure the text"))?;
+        let metrics = LineMetrics {
+            ascent: self.fonts.get_ascent(font)? as f32,
+            line_height: self.fonts.get_line_height(font)? as f32,
+        };
+
+        text.layout(
+            wrap_width,
+            metrics,
+            |s, style| self.fonts.get_styled_size(s, font, style).map(|size| size.width as f32),
+            |image| Ok((self.images.get_image_width(image)?, self.images.get_image_height(image)?)),
+        )
+    }
+
+    /***********************************************************
+     * draw_rich_text()
+     *
+     * @brief : Draw a rich text with the font of graphics, wrapped at
+     *          wrap_width pixels if given. color : the color of the text out
+     *          of the [color] tags (the color of graphics if None)
+     */
+    pub fn draw_rich_text(&mut self, text: &RichText, x: f32, y: f32, wrap_width: Option<f32>, color: Option<Color>) -> Gc2dResult<()> {
+        let font = match self.actual_font.clone() {
+            Some(font) => font,
+            None => return Err(String::from("No font to draw the text")),
+        };
+        let layout = self.layout_rich_text(text, wrap_width)?;
+        let ascent = self.fonts.get_ascent(&font)? as f32;
+        let color = color.unwrap_or(self.actual_color);
+
+        let mut line_y = y;
+        for line in layout.lines {
+            for run in line.runs {
+                match run.kind {
+                    RunKind::Text(string, style, run_color) => {
+                        let key = TextKey { font: font.clone(), string, color: run_color.unwrap_or(color), style };
+                        let params = DrawParams::new(x + run.x, line_y + line.baseline - ascent);
+                        if self.is_deferring() {
+                            self.defer(move |g| g.print_cached(key, &params));
+                        } else {
+                            self.print_cached(key, &params)?;
+                        }
+                    },
+                    // Images stand on the baseline
+                    RunKind::Image(image, height) => {
+                        self.draw_with(image, None, &DrawParams::new(x + run.x, line_y + line.baseline - height))?;
+                    },
+                }
             }
+            line_y += line.height;
+        }
+        Ok(())
+    }
+
+    /***********************************************************
+     * draw_text()
+     *
+     * @brief : Draw a text object, rendered again only if it changed
+     *          (without font, the text uses the font of graphics)
+     */
+    pub fn draw_text(&mut self, text: &mut Text, params: &DrawParams) -> Gc2dResult<()> {
+        let font = match text.get_font().or(self.actual_font.as_ref()) {
+            Some(font) => font.clone(),
+            None => return Err(String::from("No font to draw the text")),
+        };
+
+        let font_creator = self.get_fonts_creator();
+        let rendering = match self.texts.render(text, &font, &mut self.fonts, &font_creator)? {
+            Some(rendering) => rendering,
+            None => return Ok(()),
+        };
+
+        // The text may change before the queue is drawn : its rendering of
+        // now stays until the next frame
+        if self.is_deferring() {
+            let params = *params;
+            self.defer(move |g| g.draw_text_rendering(rendering, &params));
+            return Ok(());
         }
+        self.draw_text_rendering(rendering, params)
+    }
+
+    fn draw_text_rendering(&mut self, rendering: TextRendering, params: &DrawParams) -> Gc2dResult<()> {
+        let mut image = match self.texts.take(rendering) {
+            Some(image) => image,
+            None => return Ok(()),
+        };
+
+        let result = self.draw_image(ImageType::FromTexture(&mut image), params);
+        self.texts.put(rendering, image);
+        result
+    }
+
+    /***********************************************************
+     * prepare_text()
+     *
+     * @brief : Render a text object now, ex : to know its size before drawing it
+     */
+    pub fn prepare_text(&mut self, text: &mut Text) -> Gc2dResult<()> {
+        let font = match text.get_font().or(self.actual_font.as_ref()) {
+            Some(font) => font.clone(),
+            None => return Err(String::from("No font to render the text")),
+        };
+
+        let font_creator = self.get_fonts_creator();
+        self.texts.render(text, &font, &mut self.fonts, &font_creator)?;
+        Ok(())
     }
     
-    pub fn print(&mut self, text: String, x: f32, y: f32, color: Option<Color>, fonts: &mut FontsManager, ) {
-        self.print_full(text, x, y, 0f64, 1f32, 1f32, 0f32, 0f32, color, fonts);
+    pub fn print(&mut self, text: String, x: f32, y: f32, color: Option<Color>) -> Gc2dResult<()> {
+        self.print_full(text, x, y, 0f64, 1f32, 1f32, 0f32, 0f32, color)
     }
 
 }
\ No newline at end of file
@@ -1,20 +1,40 @@
-use sdl2::image::LoadTexture;
-use std::collections::HashMap;
+use sdl2::{image::LoadTexture, pixels::PixelFormatEnum, render::BlendMode};
+use std::collections::{HashMap, HashSet};
 
-use crate::gc2d::Gc2dResult;
+use crate::{gc2d::Gc2dResult, image_data::ImageData, texture_cache::destroy_texture};
 
 /*================================================================
  *                         _ I M A G E
  *================================================================*/
 pub(crate) enum ImageType<'a> {
-    ImageFromFile(String, Option<Quad>),
-    FromTexture(&'a Image),
+    FromHandle(ImageHandle, Option<Quad>),
+    FromTexture(&'a mut Image),
+}
+
+/*
+ * ImageHandle : Identify an image loaded in the images manager
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ImageHandle(u32);
+
+/*
+ * FilterMode : How a texture is sampled when it is scaled
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FilterMode {
+    // Sharp pixels, for pixel art
+    Nearest,
+    Linear,
 }
 
  pub struct Image {
     pub width: f32,
     pub height: f32,
     pub(crate) texture: sdl2::render::Texture,
+    // None : use the default filter of graphics
+    pub(crate) filter: Option<FilterMode>,
+    // Frame of the last draw, for the memory budget
+    pub(crate) last_used: u64,
 }
 
 impl Image {
@@ -25,6 +45,33 @@ impl Image {
             texture,
             height,
             width,
+            filter: None,
+            last_used: 0,
+        }
+    }
+
+    /*
+     * memory()
+     *
+     * @Brief : Estimation of the video memory used by the texture (4 bytes per pixel)
+     */
+    pub(crate) fn memory(&self) -> usize {
+        self.width as usize * self.height as usize * 4
+    }
+
+    /*
+     * set_texture_filter()
+     *
+     * @Brief : Apply the filter of the image (or default_filter) to its texture
+     */
+    pub(crate) fn set_texture_filter(&mut self, default_filter: FilterMode) {
+        let scale_mode = match self.filter.unwrap_or(default_filter) {
+            FilterMode::Nearest => sdl2::sys::SDL_ScaleMode::SDL_ScaleModeNearest,
+            FilterMode::Linear => sdl2::sys::SDL_ScaleMode::SDL_ScaleModeLinear,
+        };
+        // Not wrapped by the sdl2 crate (SDL >= 2.0.12)
+        unsafe {
+            sdl2::sys::SDL_SetTextureScaleMode(self.texture.raw(), scale_mode);
         }
     }
 }
@@ -32,7 +79,7 @@ impl Image {
 /*
  * Quad : A part of an image
  */
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub struct Quad {
     pub x: f32,
     pub y: f32,
@@ -40,12 +87,104 @@ pub struct Quad {
     pub height: f32,
 }
 
+/*
+ * NineSliceMode : How the edges and the center of a nine slice fill the target
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NineSliceMode {
+    Stretch,
+    Tile,
+}
+
+/*
+ * NineSlice : An image (or a quad of it) cut by borders insets, the corners
+ *             keep their size and the rest is stretched or tiled
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct NineSlice {
+    pub image: ImageHandle,
+    pub quad: Option<Quad>,
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub mode: NineSliceMode,
+}
+
+impl NineSlice {
+    pub fn new(image: ImageHandle, left: f32, top: f32, right: f32, bottom: f32) -> Self {
+        Self {
+            image,
+            quad: None,
+            left,
+            top,
+            right,
+            bottom,
+            mode: NineSliceMode::Stretch,
+        }
+    }
+
+    /*
+     * with_quad()
+     *
+     * @Brief : Use only a part of the image (ex: a frame of a sprite sheet)
+     */
+    pub fn with_quad(mut self, quad: Quad) -> Self {
+        self.quad = Some(quad);
+        self
+    }
+
+    pub fn with_mode(mut self, mode: NineSliceMode) -> Self {
+        self.mode = mode;
+        self
+    }
+}
+
 //=======================================================================
 //                            Images MANAGER
 //=======================================================================
+/*
+ * ImageStats : Images loaded in the images manager
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct ImageStats {
+    pub textures: usize,
+    // Estimation of the video memory used by the textures
+    pub memory_bytes: usize,
+    pub budget_bytes: Option<usize>,
+}
+
+impl ImageStats {
+    pub fn memory_mb(&self) -> f32 {
+        self.memory_bytes as f32 / (1024. * 1024.)
+    }
+}
+
+// An image loaded from a file, unloaded by the memory budget : it is loaded again when drawn
+struct EvictedImage {
+    filename: String,
+    width: f32,
+    height: f32,
+    filter: Option<FilterMode>,
+}
+
 pub(crate) struct ImagesManager {
     texture_creator: sdl2::render::TextureCreator<sdl2::video::WindowContext>,
-    images: HashMap<String, Image>,
+    images: HashMap<ImageHandle, Image>,
+    filenames: HashMap<String, ImageHandle>,
+    next_id: u32,
+
+    // Groups
+    groups: HashMap<ImageHandle, HashSet<String>>,
+    // Images loaded out of any group, never unloaded by unload_group()
+    ungrouped: HashSet<ImageHandle>,
+    actual_group: Option<String>,
+
+    // Memory budget
+    sources: HashMap<ImageHandle, String>,
+    evicted: HashMap<ImageHandle, EvictedImage>,
+    budget: Option<usize>,
+    frame: u64,
 }
 
 #[allow(dead_code)]
@@ -59,6 +198,15 @@ impl ImagesManager {
         ImagesManager {
             texture_creator,
             images: HashMap::new(),
+            filenames: HashMap::new(),
+            next_id: 0,
+            groups: HashMap::new(),
+            ungrouped: HashSet::new(),
+            actual_group: None,
+            sources: HashMap::new(),
+            evicted: HashMap::new(),
+            budget: None,
+            frame: 0,
         }
     }
 
@@ -66,37 +214,162 @@ impl ImagesManager {
      * new_image()
      * 
      * @Brief : Try to load a new image in the images manager
+     *          Loading the same file twice returns the same handle
      */
-    pub(crate) fn new_image(&mut self, filename: &str) -> Gc2dResult<()> {
+    pub(crate) fn new_image(&mut self, filename: &str) -> Gc2dResult<ImageHandle> {
 
-        if let Some(_) = self.images.get_mut(&filename.to_string()) {
-            return Ok(());
+        if let Some(handle) = self.find(filename) {
+            return Ok(handle);
         }
 
-        let texture_result = self.texture_creator.load_texture(filename.clone());
-        let texture: sdl2::render::Texture;
-        match texture_result {
-            Err(e) => {
-                return Err(e);
-            }
-            Ok(t) => {
-                texture = t;
-            }
+        let texture = self.texture_creator.load_texture(filename)?;
+
+        let handle = self.insert(Image::from_texture(texture));
+        self.filenames.insert(filename.to_string(), handle);
+        self.sources.insert(handle, filename.to_string());
+        self.enforce_budget();
+        
+        Ok(handle)
+    }
+
+    /*
+     * new_image_from_bytes()
+     * 
+     * @Brief : Try to load a new image from an encoded buffer (png, jpg, ...)
+     *          The name identifies the buffer like a filename does
+     */
+    pub(crate) fn new_image_from_bytes(&mut self, name: &str, bytes: &[u8]) -> Gc2dResult<ImageHandle> {
+
+        if let Some(handle) = self.find(name) {
+            return Ok(handle);
         }
 
-        let height = texture.query().height as f32;
-        let width = texture.query().width as f32;
+        let texture = self.texture_creator.load_texture_bytes(bytes)?;
 
-        let image = Image {
-            width,
-            height,
-            texture,
-        };
+        let handle = self.insert(Image::from_texture(texture));
+        self.filenames.insert(name.to_string(), handle);
+        self.enforce_budget();
 
-        self.images.insert(filename.to_string(), image);
-        
-        Ok(())
-    
+        Ok(handle)
+    }
+
+    /*
+     * new_image_from_rgba()
+     * 
+     * @Brief : Create a new image from raw RGBA pixels (4 bytes per pixel, row by row)
+     */
+    pub(crate) fn new_image_from_rgba(&mut self, width: u32, height: u32, pixels: &[u8]) -> Gc2dResult<ImageHandle> {
+
+        let expected = width as usize * height as usize * 4;
+        if pixels.len() != expected {
+            return Err(format!("Expected {} bytes for a {}x{} RGBA image, got {}", expected, width, height, pixels.len()));
+        }
+
+        let texture = self.create_texture(width, height, pixels)?;
+
+        let handle = self.insert(Image::from_texture(texture));
+        self.enforce_budget();
+
+        Ok(handle)
+    }
+
+    /*
+     * create_texture()
+     * 
+     * @Brief : Texture of raw RGBA pixels, not registered in the manager
+     *          (the caller has to destroy it)
+     */
+    pub(crate) fn create_texture(&self, width: u32, height: u32, pixels: &[u8]) -> Gc2dResult<sdl2::render::Texture> {
+        let mut texture = self.texture_creator
+            .create_texture_static(PixelFormatEnum::RGBA32, width, height)
+            .map_err(|e| e.to_string())?;
+
+        if let Err(e) = texture.update(None, pixels, width as usize * 4) {
+            destroy_texture(texture);
+            return Err(e.to_string());
+        }
+        texture.set_blend_mode(BlendMode::Blend);
+
+        Ok(texture)
+    }
+
+    /*
+     * create_target_texture()
+     * 
+     * @Brief : Texture which can be drawn into, not registered in the manager
+     */
+    pub(crate) fn create_target_texture(&self, width: u32, height: u32) -> Gc2dResult<sdl2::render::Texture> {
+        let mut texture = self.texture_creator
+            .create_texture_target(PixelFormatEnum::RGBA32, width, height)
+            .map_err(|e| e.to_string())?;
+        texture.set_blend_mode(BlendMode::Blend);
+        Ok(texture)
+    }
+
+    /*
+     * new_canvas()
+     * 
+     * @Brief : Create an image which can be drawn into (a render target)
+     */
+    pub(crate) fn new_canvas(&mut self, width: u32, height: u32) -> Gc2dResult<ImageHandle> {
+        let texture = self.create_target_texture(width, height)?;
+
+        let handle = self.insert(Image::from_texture(texture));
+        self.enforce_budget();
+
+        Ok(handle)
+    }
+
+    /*
+     * new_image_from_data()
+     * 
+     * @Brief : Create a new image from an image data
+     */
+    pub(crate) fn new_image_from_data(&mut self, data: &ImageData) -> Gc2dResult<ImageHandle> {
+        self.new_image_from_rgba(data.get_width(), data.get_height(), data.get_pixels())
+    }
+
+    /*
+     * new_image_decoded()
+     * 
+     * @Brief : Register a file decoded elsewhere (ex : by the asset loader),
+     *          like new_image() would have loaded it
+     */
+    pub(crate) fn new_image_decoded(&mut self, filename: &str, data: &ImageData) -> Gc2dResult<ImageHandle> {
+
+        if let Some(handle) = self.find(filename) {
+            return Ok(handle);
+        }
+
+        let handle = self.new_image_from_data(data)?;
+        self.filenames.insert(filename.to_string(), handle);
+        self.sources.insert(handle, filename.to_string());
+
+        Ok(handle)
+    }
+
+    // Handle of an image already loaded with this name, joining the actual group
+    fn find(&mut self, name: &str) -> Option<ImageHandle> {
+        let handle = *self.filenames.get(name)?;
+        if !self.images.contains_key(&handle) && !self.evicted.contains_key(&handle) {
+            return None;
+        }
+        self.join_actual_group(handle);
+        Some(handle)
+    }
+
+    /*
+     * insert()
+     * 
+     * @Brief : Register an image and give it a new handle
+     */
+    pub(crate) fn insert(&mut self, mut image: Image) -> ImageHandle {
+        let handle = ImageHandle(self.next_id);
+        self.next_id += 1;
+        image.last_used = self.frame;
+        self.images.insert(handle, image);
+        self.join_actual_group(handle);
+        handle
     }
 
     /*
@@ -104,29 +377,198 @@ impl ImagesManager {
      * 
      * @Brief : Get a image from the image manager
      */
-    pub fn get_image(&self, filename: &str) -> Option<&Image> {
-        self.images.get(&filename.to_string())
+    pub fn get_image(&self, handle: ImageHandle) -> Gc2dResult<&Image> {
+        self.images
+            .get(&handle)
+            .ok_or_else(|| format!("Image {:?} is not loaded", handle))
+    }
+
+    /*
+     * get_image_mut()
+     * 
+     * @Brief : Get a image to draw it, an image unloaded by the memory budget is loaded again
+     */
+    pub(crate) fn get_image_mut(&mut self, handle: ImageHandle) -> Gc2dResult<&mut Image> {
+
+        if let Some(evicted) = self.evicted.remove(&handle) {
+            let texture = self.texture_creator.load_texture(&evicted.filename)?;
+            let mut image = Image::from_texture(texture);
+            image.filter = evicted.filter;
+            image.last_used = self.frame;
+            self.images.insert(handle, image);
+            self.enforce_budget();
+        }
+
+        let frame = self.frame;
+        let image = self.images
+            .get_mut(&handle)
+            .ok_or_else(|| format!("Image {:?} is not loaded", handle))?;
+        image.last_used = frame;
+        Ok(image)
     }
 
     /*
      * get_image_height()
      */
-    pub fn get_image_height(&self, filename: &str) -> f32 {
-        if let Some(image) = self.images.get(&filename.to_string()) {
-            image.height
-        } else {
-            0.
+    pub fn get_image_height(&self, handle: ImageHandle) -> Gc2dResult<f32> {
+        match self.evicted.get(&handle) {
+            Some(evicted) => Ok(evicted.height),
+            None => Ok(self.get_image(handle)?.height),
         }
     }
 
     /*
      * get_image_width())
      */
-    pub fn get_image_width(&self, filename: &str) -> f32 {
-        if let Some(image) = self.images.get(&filename.to_string()) {
-            image.width
-        } else {
-            0.
+    pub fn get_image_width(&self, handle: ImageHandle) -> Gc2dResult<f32> {
+        match self.evicted.get(&handle) {
+            Some(evicted) => Ok(evicted.width),
+            None => Ok(self.get_image(handle)?.width),
         }
     }
-}
\ No newline at end of file
+
+    //=======================================================================
+    //                             UNLOAD
+    //=======================================================================
+    /*
+     * unload_image()
+     * 
+     * @Brief : Free the texture of an image, its handle becomes invalid
+     */
+    pub(crate) fn unload_image(&mut self, handle: ImageHandle) -> Gc2dResult<()> {
+        let image = self.images.remove(&handle);
+        let evicted = self.evicted.remove(&handle);
+
+        if image.is_none() && evicted.is_none() {
+            return Err(format!("Image {:?} is not loaded", handle));
+        }
+
+        if let Some(image) = image {
+            destroy_texture(image.texture);
+        }
+
+        self.filenames.retain(|_, h| *h != handle);
+        self.sources.remove(&handle);
+        self.groups.remove(&handle);
+        self.ungrouped.remove(&handle);
+
+        Ok(())
+    }
+
+    /*
+     * begin_group()
+     * 
+     * @Brief : Images loaded until end_group() belong to the group name
+     */
+    pub(crate) fn begin_group(&mut self, name: &str) {
+        self.actual_group = Some(name.to_string());
+    }
+
+    pub(crate) fn end_group(&mut self) {
+        self.actual_group = None;
+    }
+
+    fn join_actual_group(&mut self, handle: ImageHandle) {
+        match &self.actual_group {
+            Some(group) => {
+                self.groups.entry(handle).or_default().insert(group.clone());
+            },
+            None => {
+                self.ungrouped.insert(handle);
+            },
+        }
+    }
+
+    /*
+     * unload_group()
+     * 
+     * @Brief : Unload the images of a group, except the ones still used by
+     *          another group or loaded out of any group. Returns the number of
+     *          images unloaded.
+     */
+    pub(crate) fn unload_group(&mut self, name: &str) -> usize {
+        let mut unused = Vec::new();
+        for (handle, groups) in self.groups.iter_mut() {
+            if groups.remove(name) && groups.is_empty() && !self.ungrouped.contains(handle) {
+                unused.push(*handle);
+            }
+        }
+        self.groups.retain(|_, groups| !groups.is_empty());
+
+        for handle in unused.iter() {
+            let _ = self.unload_image(*handle);
+        }
+        unused.len()
+    }
+
+    //=======================================================================
+    //                          MEMORY BUDGET
+    //=======================================================================
+    /*
+     * set_budget()
+     * 
+     * @Brief : Maximum video memory for the images (None : no limit). When it
+     *          is exceeded, the images loaded from files which were not drawn
+     *          for the longest time are unloaded, and loaded again when drawn.
+     */
+    pub(crate) fn set_budget(&mut self, budget: Option<usize>) {
+        self.budget = budget;
+        self.enforce_budget();
+    }
+
+    /*
+     * next_frame()
+     * 
+     * @Brief : Call once per frame, the images drawn in this frame are never evicted
+     */
+    pub(crate) fn next_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    fn enforce_budget(&mut self) {
+        let budget = match self.budget {
+            Some(budget) => budget,
+            None => return,
+        };
+
+        let mut memory: usize = self.images.values().map(|image| image.memory()).sum();
+        if memory <= budget {
+            return;
+        }
+
+        // Only images which can be loaded again, least recently used first
+        let mut candidates: Vec<(u64, ImageHandle)> = self.images
+            .iter()
+            .filter(|(handle, image)| self.sources.contains_key(handle) && image.last_used < self.frame)
+            .map(|(handle, image)| (image.last_used, *handle))
+            .collect();
+        candidates.sort_by_key(|(last_used, _)| *last_used);
+
+        for (_, handle) in candidates {
+            if memory <= budget {
+                break;
+            }
+            if let Some(image) = self.images.remove(&handle) {
+                memory -= image.memory();
+                self.evicted.insert(handle, EvictedImage {
+                    filename: self.sources[&handle].clone(),
+                    width: image.width,
+                    height: image.height,
+                    filter: image.filter,
+                });
+                destroy_texture(image.texture);
+            }
+        }
+    }
+
+    /*
+     * get_stats()
+     */
+    pub(crate) fn get_stats(&self) -> ImageStats {
+        ImageStats {
+            textures: self.images.len(),
+            memory_bytes: self.images.values().map(|image| image.memory()).sum(),
+            budget_bytes: self.budget,
+        }
+    }
+}
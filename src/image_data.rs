@@ -0,0 +1,331 @@
+use sdl2::{image::{ImageRWops, LoadSurface, SaveSurface}, pixels::PixelFormatEnum, rwops::RWops, surface::Surface};
+
+use crate::{color::Color, gc2d::Gc2dResult, image::Quad};
+
+/*================================================================
+ *                      I M A G E   D A T A
+ *================================================================*/
+/*
+ * ImageData : RGBA pixels kept in memory (4 bytes per pixel, row by row)
+ */
+#[derive(Clone)]
+pub struct ImageData {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl ImageData {
+    /*
+     * new()
+     *
+     * @Brief : Create a new transparent image data
+     */
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    /*
+     * from_rgba()
+     *
+     * @Brief : Create an image data from raw RGBA pixels
+     */
+    pub fn from_rgba(width: u32, height: u32, pixels: Vec<u8>) -> Gc2dResult<Self> {
+        let expected = width as usize * height as usize * 4;
+        if pixels.len() != expected {
+            return Err(format!("Expected {} bytes for a {}x{} RGBA image, got {}", expected, width, height, pixels.len()));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /*
+     * from_file()
+     *
+     * @Brief : Load an image data from a file (png, jpg, ...)
+     */
+    pub fn from_file(filename: &str) -> Gc2dResult<Self> {
+        let surface = Surface::from_file(filename)?;
+        Self::from_surface(&surface)
+    }
+
+    /*
+     * from_bytes()
+     *
+     * @Brief : Load an image data from an encoded buffer (png, jpg, ...)
+     */
+    pub fn from_bytes(bytes: &[u8]) -> Gc2dResult<Self> {
+        let surface = RWops::from_bytes(bytes)?.load()?;
+        Self::from_surface(&surface)
+    }
+
+    fn from_surface(surface: &Surface) -> Gc2dResult<Self> {
+        let surface = surface.convert_format(PixelFormatEnum::RGBA32)?;
+
+        let width = surface.width();
+        let height = surface.height();
+        let pitch = surface.pitch() as usize;
+        let row = width as usize * 4;
+
+        let mut pixels = Vec::with_capacity(row * height as usize);
+        surface.with_lock(|data| {
+            for y in 0..height as usize {
+                pixels.extend_from_slice(&data[y * pitch..y * pitch + row]);
+            }
+        });
+
+        Self::from_rgba(width, height, pixels)
+    }
+
+    /*
+     * save_png()
+     *
+     * @Brief : Save the image data in a png file
+     */
+    pub fn save_png(&self, filename: &str) -> Gc2dResult<()> {
+        let mut pixels = self.pixels.clone();
+        let surface = Surface::from_data(&mut pixels, self.width, self.height, self.width * 4, PixelFormatEnum::RGBA32)?;
+        surface.save(filename)
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    /*
+     * get_pixels()
+     *
+     * @Brief : Raw RGBA pixels
+     */
+    pub fn get_pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    fn index(&self, x: u32, y: u32) -> Option<usize> {
+        if x < self.width && y < self.height {
+            Some((y as usize * self.width as usize + x as usize) * 4)
+        } else {
+            None
+        }
+    }
+
+    //=======================================================================
+    //                             PIXELS
+    //=======================================================================
+    /*
+     * get_pixel()
+     *
+     * @Brief : Color of a pixel, None when outside of the image
+     */
+    pub fn get_pixel(&self, x: u32, y: u32) -> Option<Color> {
+        self.index(x, y).map(|i| Color {
+            r: self.pixels[i],
+            g: self.pixels[i + 1],
+            b: self.pixels[i + 2],
+            a: self.pixels[i + 3],
+        })
+    }
+
+    /*
+     * set_pixel()
+     *
+     * @Brief : Change the color of a pixel, ignored when outside of the image
+     */
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: Color) {
+        if let Some(i) = self.index(x, y) {
+            self.pixels[i] = color.r;
+            self.pixels[i + 1] = color.g;
+            self.pixels[i + 2] = color.b;
+            self.pixels[i + 3] = color.a;
+        }
+    }
+
+    /*
+     * map_pixels()
+     *
+     * @Brief : Replace every pixel by the result of f(x, y, color)
+     */
+    pub fn map_pixels<F: FnMut(u32, u32, Color) -> Color>(&mut self, mut f: F) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if let Some(color) = self.get_pixel(x, y) {
+                    self.set_pixel(x, y, f(x, y, color));
+                }
+            }
+        }
+    }
+
+    //=======================================================================
+    //                             COPY
+    //=======================================================================
+    /*
+     * paste()
+     *
+     * @Brief : Copy source (or a part of it) at (dx, dy), pixels are replaced
+     */
+    pub fn paste(&mut self, source: &ImageData, dx: i32, dy: i32, quad: Option<Quad>) {
+        self.copy_from(source, dx, dy, quad, |_, src| src);
+    }
+
+    /*
+     * blit()
+     *
+     * @Brief : Draw source at (dx, dy), using its alpha
+     */
+    pub fn blit(&mut self, source: &ImageData, dx: i32, dy: i32) {
+        self.copy_from(source, dx, dy, None, |dst, src| src.over(dst));
+    }
+
+    fn copy_from<F: Fn(Color, Color) -> Color>(&mut self, source: &ImageData, dx: i32, dy: i32, quad: Option<Quad>, f: F) {
+        let (sx, sy, sw, sh) = match quad {
+            Some(q) => (q.x as u32, q.y as u32, q.width as u32, q.height as u32),
+            None => (0, 0, source.width, source.height),
+        };
+
+        for y in 0..sh {
+            for x in 0..sw {
+                // Out of the source or of this image, ex : a huge quad
+                let (px, py, tx, ty) = match (sx.checked_add(x), sy.checked_add(y), dx.checked_add_unsigned(x), dy.checked_add_unsigned(y)) {
+                    (Some(px), Some(py), Some(tx), Some(ty)) => (px, py, tx, ty),
+                    _ => continue,
+                };
+                if tx < 0 || ty < 0 {
+                    continue;
+                }
+                if let (Some(src), Some(dst)) = (source.get_pixel(px, py), self.get_pixel(tx as u32, ty as u32)) {
+                    self.set_pixel(tx as u32, ty as u32, f(dst, src));
+                }
+            }
+        }
+    }
+
+    //=======================================================================
+    //                          TRANSFORMATIONS
+    //=======================================================================
+    /*
+     * crop()
+     *
+     * @Brief : New image data with a part of this one
+     */
+    pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> ImageData {
+        let mut data = ImageData::new(width, height);
+        data.paste(self, 0, 0, Some(Quad { x: x as f32, y: y as f32, width: width as f32, height: height as f32 }));
+        data
+    }
+
+    /*
+     * flip_horizontal()
+     */
+    pub fn flip_horizontal(&mut self) {
+        let row = self.width as usize * 4;
+        if row == 0 {
+            return;
+        }
+        for line in self.pixels.chunks_exact_mut(row) {
+            for x in 0..self.width as usize / 2 {
+                let mirror = self.width as usize - 1 - x;
+                for c in 0..4 {
+                    line.swap(x * 4 + c, mirror * 4 + c);
+                }
+            }
+        }
+    }
+
+    /*
+     * flip_vertical()
+     */
+    pub fn flip_vertical(&mut self) {
+        let row = self.width as usize * 4;
+        let height = self.height as usize;
+        for y in 0..height / 2 {
+            let (top, bottom) = self.pixels.split_at_mut((height - 1 - y) * row);
+            top[y * row..(y + 1) * row].swap_with_slice(&mut bottom[..row]);
+        }
+    }
+
+    /*
+     * resize()
+     *
+     * @Brief : New image data scaled to width x height (nearest neighbour)
+     */
+    pub fn resize(&self, width: u32, height: u32) -> ImageData {
+        let mut data = ImageData::new(width, height);
+        if self.width == 0 || self.height == 0 {
+            return data;
+        }
+        data.map_pixels(|x, y, _| {
+            let sx = (x as u64 * self.width as u64 / width as u64) as u32;
+            let sy = (y as u64 * self.height as u64 / height as u64) as u32;
+            self.get_pixel(sx, sy).unwrap_or(Color::TRANSPARENT)
+        });
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blit_over_transparent_keeps_the_color() {
+        let mut data = ImageData::new(1, 1);
+        let mut source = ImageData::new(1, 1);
+        source.set_pixel(0, 0, Color::rgba(255, 0, 0, 128));
+
+        data.blit(&source, 0, 0);
+        assert_eq!(data.get_pixel(0, 0), Some(Color::rgba(255, 0, 0, 128)));
+    }
+
+    #[test]
+    fn blit_over_opaque_mixes_the_colors() {
+        let mut data = ImageData::new(1, 1);
+        data.set_pixel(0, 0, Color::rgb(0, 0, 255));
+        let mut source = ImageData::new(1, 1);
+        source.set_pixel(0, 0, Color::rgba(255, 0, 0, 51));
+
+        data.blit(&source, 0, 0);
+        assert_eq!(data.get_pixel(0, 0), Some(Color::rgb(51, 0, 204)));
+    }
+
+    #[test]
+    fn blit_is_clipped_to_the_image() {
+        let mut data = ImageData::new(2, 2);
+        let mut source = ImageData::new(2, 2);
+        source.map_pixels(|_, _, _| Color::WHITE);
+
+        data.blit(&source, 1, -1);
+        assert_eq!(data.get_pixel(1, 0), Some(Color::WHITE));
+        assert_eq!(data.get_pixel(0, 0), Some(Color::TRANSPARENT));
+        assert_eq!(data.get_pixel(1, 1), Some(Color::TRANSPARENT));
+    }
+
+    #[test]
+    fn crop_copies_a_part() {
+        let mut data = ImageData::new(3, 3);
+        data.set_pixel(2, 1, Color::RED);
+
+        let part = data.crop(1, 1, 2, 2);
+        assert_eq!((part.get_width(), part.get_height()), (2, 2));
+        assert_eq!(part.get_pixel(1, 0), Some(Color::RED));
+        assert_eq!(part.get_pixel(0, 0), Some(Color::TRANSPARENT));
+    }
+
+    #[test]
+    fn crop_out_of_the_image_is_transparent() {
+        let data = ImageData::new(2, 2);
+        let part = data.crop(u32::MAX - 1, u32::MAX - 1, 4, 4);
+        assert!(part.get_pixels().iter().all(|b| *b == 0));
+    }
+}
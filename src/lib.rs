@@ -1,12 +1,27 @@
 
+pub mod animation;
 pub mod audio;
+pub mod bitmap_font;
 pub mod context;
+pub mod draw_queue;
 pub mod color;
 pub mod event;
 pub mod fonts;
 pub mod graphics;
 pub mod gc2d;
+pub mod gradient;
 pub mod image;
+pub mod image_data;
 pub mod keyboard;
+pub mod loader;
 pub mod mouse;
+pub mod palette;
+pub mod particles;
+pub mod path;
+pub mod rich_text;
+pub mod sprite_sheet;
+pub mod text;
+pub mod texture_cache;
+pub mod tilemap;
+pub mod transition;
 pub mod window;
\ No newline at end of file
@@ -0,0 +1,269 @@
+use std::{collections::HashMap, fs, sync::mpsc::{self, Receiver, TryRecvError}, thread::{self, JoinHandle}};
+
+use crate::{audio::{AudioManager, AudioType}, fonts::Font, gc2d::{Gc2d, Gc2dResult}, image::ImageHandle, image_data::ImageData};
+
+/*================================================================
+ *                     A S S E T   L O A D E R
+ *================================================================*/
+/*
+ * Images are read and decoded on a worker thread, then update() creates
+ * their textures on the main thread, a few per frame, so that the game can
+ * draw a loading screen meanwhile.
+ *
+ * Only images are loaded in the background : SDL fonts and sounds borrow
+ * their data for their whole life, so they are loaded from their files by
+ * update(), on the main thread. The worker only checks that their files
+ * exist, a missing file is an error of the loader like a broken image.
+ *
+ *   load()   : loader.add_image(...); loader.start();
+ *   update() : loader.update(gc2d, audio_manager)?;
+ *   draw()   : draw a bar of loader.progress() until loader.is_done()
+ */
+#[derive(Clone)]
+enum Asset {
+    Image(String),
+    Font(String, u16),
+    Sound(String, AudioType),
+}
+
+impl Asset {
+    fn filename(&self) -> &str {
+        match self {
+            Asset::Image(filename) | Asset::Font(filename, _) | Asset::Sound(filename, _) => filename,
+        }
+    }
+}
+
+// What the worker sends back for an asset
+enum Decoded {
+    Image(ImageData),
+    // The file exists, it is loaded on the main thread (fonts and sounds)
+    File,
+}
+
+pub struct AssetLoader {
+    queue: Vec<Asset>,
+    receiver: Option<Receiver<(Asset, Gc2dResult<Decoded>)>>,
+    worker: Option<JoinHandle<()>>,
+    total: usize,
+    done: usize,
+    uploads_per_frame: usize,
+    images: HashMap<String, ImageHandle>,
+    fonts: HashMap<(String, u16), Font>,
+    errors: Vec<String>,
+}
+
+impl Default for AssetLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AssetLoader {
+    pub fn new() -> Self {
+        Self {
+            queue: Vec::new(),
+            receiver: None,
+            worker: None,
+            total: 0,
+            done: 0,
+            uploads_per_frame: 4,
+            images: HashMap::new(),
+            fonts: HashMap::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    //=======================================================================
+    //                             QUEUE
+    //=======================================================================
+    /*
+     * add_image()
+     *
+     * @Brief : Queue an image, decoded on the worker thread
+     */
+    pub fn add_image(&mut self, filename: &str) {
+        self.add(Asset::Image(filename.to_string()));
+    }
+
+    /*
+     * add_font()
+     *
+     * @Brief : Queue a font, loaded on the main thread by update()
+     */
+    pub fn add_font(&mut self, filename: &str, point_size: u16) {
+        self.add(Asset::Font(filename.to_string(), point_size));
+    }
+
+    /*
+     * add_sound()
+     *
+     * @Brief : Queue a sound, loaded on the main thread by update()
+     */
+    pub fn add_sound(&mut self, filename: &str, audio_type: AudioType) {
+        self.add(Asset::Sound(filename.to_string(), audio_type));
+    }
+
+    fn add(&mut self, asset: Asset) {
+        self.queue.push(asset);
+        self.total += 1;
+    }
+
+    /*
+     * set_uploads_per_frame()
+     *
+     * @Brief : Maximum number of assets created on the main thread by update()
+     */
+    pub fn set_uploads_per_frame(&mut self, count: usize) {
+        self.uploads_per_frame = count.max(1);
+    }
+
+    /*
+     * start()
+     *
+     * @Brief : Start reading the queued assets on a worker thread
+     *          Assets added while a worker runs are read after it, by update()
+     */
+    pub fn start(&mut self) {
+        if self.queue.is_empty() {
+            return;
+        }
+
+        // A worker is running : update() starts the queue when it is over
+        if self.receiver.is_some() {
+            return;
+        }
+
+        let assets: Vec<Asset> = self.queue.drain(..).collect();
+        let (sender, receiver) = mpsc::channel();
+
+        self.worker = Some(thread::spawn(move || {
+            for asset in assets {
+                let decoded = match &asset {
+                    Asset::Image(filename) => ImageData::from_file(filename).map(Decoded::Image),
+                    Asset::Font(filename, _) | Asset::Sound(filename, _) => fs::metadata(filename)
+                        .map(|_| Decoded::File)
+                        .map_err(|e| format!("{}: {}", filename, e)),
+                };
+
+                // The loader was dropped
+                if sender.send((asset, decoded)).is_err() {
+                    break;
+                }
+            }
+        }));
+        self.receiver = Some(receiver);
+    }
+
+    //=======================================================================
+    //                             UPLOAD
+    //=======================================================================
+    /*
+     * update()
+     *
+     * @Brief : Create the assets read by the worker (call it once per frame)
+     *          An asset which failed is counted as done and its error returned
+     */
+    pub fn update(&mut self, gc2d: &mut Gc2d, audio_manager: &mut AudioManager) -> Gc2dResult<()> {
+        let mut first_error = None;
+
+        for _ in 0..self.uploads_per_frame {
+            let received = match &self.receiver {
+                Some(receiver) => receiver.try_recv(),
+                None => break,
+            };
+
+            let (asset, decoded) = match received {
+                Ok(result) => result,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    // Worker over : start the assets queued since
+                    self.receiver = None;
+                    if let Some(worker) = self.worker.take() {
+                        let _ = worker.join();
+                    }
+                    self.start();
+                    break;
+                },
+            };
+
+            self.done += 1;
+
+            let result = decoded.and_then(|decoded| self.upload(gc2d, audio_manager, &asset, decoded));
+            if let Err(e) = result {
+                self.errors.push(e.clone());
+                first_error.get_or_insert(e);
+            }
+        }
+
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    fn upload(&mut self, gc2d: &mut Gc2d, audio_manager: &mut AudioManager, asset: &Asset, decoded: Decoded) -> Gc2dResult<()> {
+        match (asset, decoded) {
+            (Asset::Image(filename), Decoded::Image(data)) => {
+                let handle = gc2d.graphics.new_image_decoded(filename, &data)?;
+                self.images.insert(filename.clone(), handle);
+            },
+            (Asset::Font(filename, point_size), _) => {
+                let font = gc2d.graphics.new_font(filename, *point_size)?;
+                self.fonts.insert((filename.clone(), *point_size), font);
+            },
+            (Asset::Sound(filename, audio_type), _) => {
+                gc2d.audio.new_source(filename, audio_manager, audio_type.clone());
+            },
+            (asset, _) => return Err(format!("{}: unexpected data", asset.filename())),
+        }
+        Ok(())
+    }
+
+    //=======================================================================
+    //                             STATE
+    //=======================================================================
+    /*
+     * progress()
+     *
+     * @Brief : From 0. to 1. (1. when nothing is queued)
+     */
+    pub fn progress(&self) -> f32 {
+        if self.total == 0 {
+            1.
+        } else {
+            self.done as f32 / self.total as f32
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done == self.total
+    }
+
+    /*
+     * get_image()
+     *
+     * @Brief : Handle of a loaded image (graphics.new_image() returns it too)
+     */
+    pub fn get_image(&self, filename: &str) -> Option<ImageHandle> {
+        self.images.get(filename).copied()
+    }
+
+    /*
+     * get_font()
+     *
+     * @Brief : A loaded font (graphics.new_font() returns it too)
+     */
+    pub fn get_font(&self, filename: &str, point_size: u16) -> Option<Font> {
+        self.fonts.get(&(filename.to_string(), point_size)).cloned()
+    }
+
+    /*
+     * get_errors()
+     *
+     * @Brief : Errors of the assets which could not be loaded
+     */
+    pub fn get_errors(&self) -> &[String] {
+        &self.errors
+    }
+}
@@ -32,7 +32,7 @@ impl EventLoop for MyTestApp {
         gc2d.audio.play(audio_manager, "assets/sounds/cool.mp3");
         gc2d.audio.new_source("assets/sounds/explosion.wav", audio_manager, gc2d::audio::AudioType::Effect);
         gc2d.graphics.set_background_color(Color::WHITE);
-        gc2d.graphics.new_font("assets/fonts/PixelMaster.ttf", 25);
+        gc2d.graphics.new_font("assets/fonts/PixelMaster.ttf", 25)?;
         
         Ok(())
     }
@@ -44,9 +44,9 @@ impl EventLoop for MyTestApp {
         Ok(())
     }
 
-    fn draw(&mut self, gc2d: &mut Gc2d, fonts: &mut gc2d::fonts::FontsManager, _dt: f32) -> Gc2dResult<()> {
-        gc2d.graphics.circle(gc2d::graphics::DrawMode::Line, self.x, 50f32, 20f32, Some(Color::BLUE));
-        gc2d.graphics.print(format!("x: {}", gc2d.mouse.x), 10., 10., Some(Color::RED), fonts);
+    fn draw(&mut self, gc2d: &mut Gc2d, _dt: f32) -> Gc2dResult<()> {
+        gc2d.graphics.circle(gc2d::graphics::DrawMode::Line, self.x, 50f32, 20f32, Some(Color::BLUE))?;
+        gc2d.graphics.print(format!("x: {}", gc2d.mouse.x), 10., 10., Some(Color::RED))?;
         Ok(())
     }
 
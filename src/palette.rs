@@ -0,0 +1,216 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::{color::Color, gc2d::Gc2dResult, image_data::ImageData};
+
+/*================================================================
+ *                         P A L E T T E
+ *================================================================*/
+/*
+ * Palette : Ordered colors, loaded from a palette file
+ *
+ *   .gpl : GIMP palette ("R G B name" per line)
+ *   .hex : one "rrggbb" per line (Lospec export)
+ *   .png : the colors of the pixels from left to right, top to bottom
+ *          (a strip of 1x1 or larger swatches)
+ */
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Palette {
+    pub name: String,
+    colors: Vec<Color>,
+}
+
+impl Palette {
+    pub fn new(colors: Vec<Color>) -> Self {
+        Self {
+            name: String::new(),
+            colors,
+        }
+    }
+
+    //=======================================================================
+    //                             LOADING
+    //=======================================================================
+    /*
+     * from_file()
+     *
+     * @Brief : Load a .gpl, .hex or .png palette, from its extension
+     */
+    pub fn from_file(filename: &str) -> Gc2dResult<Self> {
+        let extension = Path::new(filename)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+
+        let mut palette = match extension.as_deref() {
+            Some("gpl") => Palette::from_gpl(&read(filename)?),
+            Some("hex") => Palette::from_hex(&read(filename)?),
+            Some("png") => Ok(Palette::from_image_data(&ImageData::from_file(filename)?)),
+            _ => Err(String::from("Unknown palette format, expected .gpl, .hex or .png")),
+        }
+        .map_err(|e| format!("{}: {}", filename, e))?;
+
+        if palette.name.is_empty() {
+            palette.name = Path::new(filename)
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default()
+                .to_string();
+        }
+        Ok(palette)
+    }
+
+    /*
+     * from_gpl()
+     *
+     * @Brief : Parse the text of a GIMP palette
+     */
+    pub fn from_gpl(text: &str) -> Gc2dResult<Self> {
+        let mut lines = text.lines();
+        if lines.next().map(|l| l.trim()) != Some("GIMP Palette") {
+            return Err(String::from("Missing the \"GIMP Palette\" header"));
+        }
+
+        let mut palette = Palette::default();
+        for (number, line) in lines.enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with("Columns:") {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix("Name:") {
+                palette.name = name.trim().to_string();
+                continue;
+            }
+
+            let components: Vec<u8> = line
+                .split_whitespace()
+                .take(3)
+                .map(|v| v.parse::<u8>())
+                .collect::<Result<_, _>>()
+                .map_err(|_| format!("Line {}: expected \"R G B\", got \"{}\"", number + 2, line))?;
+            if components.len() < 3 {
+                return Err(format!("Line {}: expected \"R G B\", got \"{}\"", number + 2, line));
+            }
+            palette.colors.push(Color::rgb(components[0], components[1], components[2]));
+        }
+        Ok(palette)
+    }
+
+    /*
+     * from_hex()
+     *
+     * @Brief : Parse a hex palette, one color per line
+     */
+    pub fn from_hex(text: &str) -> Gc2dResult<Self> {
+        let colors = text
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty() && !l.starts_with(';'))
+            .map(Color::from_hex)
+            .collect::<Gc2dResult<Vec<Color>>>()?;
+        Ok(Palette::new(colors))
+    }
+
+    /*
+     * from_image_data()
+     *
+     * @Brief : Colors of an image in reading order, each once
+     *          (transparent pixels are skipped)
+     */
+    pub fn from_image_data(data: &ImageData) -> Self {
+        let mut palette = Palette::default();
+        for y in 0..data.get_height() {
+            for x in 0..data.get_width() {
+                match data.get_pixel(x, y) {
+                    Some(color) if color.a > 0 && !palette.colors.contains(&color) => palette.colors.push(color),
+                    _ => {},
+                }
+            }
+        }
+        palette
+    }
+
+    //=======================================================================
+    //                             COLORS
+    //=======================================================================
+    pub fn get(&self, index: usize) -> Option<Color> {
+        self.colors.get(index).copied()
+    }
+
+    pub fn get_colors(&self) -> &[Color] {
+        &self.colors
+    }
+
+    pub fn add(&mut self, color: Color) {
+        self.colors.push(color);
+    }
+
+    /*
+     * find()
+     *
+     * @Brief : Index of a color (alpha ignored)
+     */
+    pub fn find(&self, color: Color) -> Option<usize> {
+        self.colors
+            .iter()
+            .position(|c| c.r == color.r && c.g == color.g && c.b == color.b)
+    }
+
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    //=======================================================================
+    //                             SWAP
+    //=======================================================================
+    /*
+     * swap()
+     *
+     * @Brief : Copy of an image data where the color n of this palette becomes
+     *          the color n of target (the alpha of the pixels is kept).
+     *          Colors out of the palettes are not changed.
+     */
+    pub fn swap(&self, data: &ImageData, target: &Palette) -> ImageData {
+        let mapping: HashMap<(u8, u8, u8), Color> = self
+            .colors
+            .iter()
+            .zip(target.colors.iter())
+            .rev()
+            .map(|(from, to)| ((from.r, from.g, from.b), *to))
+            .collect();
+
+        let mut swapped = data.clone();
+        swapped.map_pixels(|_, _, color| match mapping.get(&(color.r, color.g, color.b)) {
+            Some(to) => to.with_alpha(color.a),
+            None => color,
+        });
+        swapped
+    }
+}
+
+fn read(filename: &str) -> Gc2dResult<String> {
+    fs::read_to_string(filename).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_gpl() {
+        let text = "GIMP Palette\nName: Sunset\nColumns: 2\n# comment\n255   0  64\tred\n  0 128 255\n\n";
+        let palette = Palette::from_gpl(text).unwrap();
+        assert_eq!(palette.name, "Sunset");
+        assert_eq!(palette.get_colors(), [Color::rgb(255, 0, 64), Color::rgb(0, 128, 255)]);
+    }
+
+    #[test]
+    fn from_gpl_errors() {
+        assert!(Palette::from_gpl("255 0 0\n").is_err());
+        assert!(Palette::from_gpl("GIMP Palette\n255 0\n").is_err());
+        assert!(Palette::from_gpl("GIMP Palette\n256 0 0\n").is_err());
+    }
+}
@@ -0,0 +1,410 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{color::Color, gc2d::Gc2dResult, graphics::{DrawMode, DrawParams, Graphics}, image::{ImageHandle, Quad}};
+
+// Width in pixels of the particles when no size is set
+pub const DEFAULT_SIZE: f32 = 8.;
+
+/*================================================================
+ *                        P A R T I C L E S
+ *================================================================*/
+/*
+ * ParticleShape : What is drawn for each particle
+ *                 The size of a particle is its width in pixels, whatever its shape
+ *                 (an image keeps its aspect ratio, a circle is size wide)
+ */
+#[derive(Clone, Copy, Debug)]
+pub enum ParticleShape {
+    Image(ImageHandle, Option<Quad>),
+    Rectangle,
+    Circle,
+}
+
+struct Particle {
+    x: f32,
+    y: f32,
+    vx: f32,
+    vy: f32,
+    ax: f32,
+    ay: f32,
+    rotation: f32,
+    spin: f32,
+    life: f32,
+    lifetime: f32,
+}
+
+// Small xorshift generator, enough for particles
+struct Random(u64);
+
+impl Random {
+    fn new() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0x2545_F491_4F6C_DD1D);
+        Self(seed | 1)
+    }
+
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next()
+    }
+}
+
+pub struct ParticleSystem {
+    shape: ParticleShape,
+    particles: Vec<Particle>,
+    max_particles: usize,
+    random: Random,
+
+    // Emitter
+    x: f32,
+    y: f32,
+    area_width: f32,
+    area_height: f32,
+    active: bool,
+    emission_rate: f32,
+    emission_timer: f32,
+    // Seconds left before the emitter stops, negative = forever
+    emitter_lifetime: f32,
+
+    // Particles settings
+    lifetime: (f32, f32),
+    speed: (f32, f32),
+    direction: f32,
+    spread: f32,
+    gravity: (f32, f32),
+    linear_acceleration: (f32, f32, f32, f32),
+    rotation: (f32, f32),
+    spins: Vec<f32>,
+    sizes: Vec<f32>,
+    colors: Vec<Color>,
+}
+
+impl ParticleSystem {
+    /*
+     * new()
+     *
+     * @Brief : Create a stopped particle system of at most max_particles
+     */
+    pub fn new(shape: ParticleShape, max_particles: usize) -> Self {
+        Self {
+            shape,
+            particles: Vec::with_capacity(max_particles),
+            max_particles,
+            random: Random::new(),
+            x: 0.,
+            y: 0.,
+            area_width: 0.,
+            area_height: 0.,
+            active: false,
+            emission_rate: 0.,
+            emission_timer: 0.,
+            emitter_lifetime: -1.,
+            lifetime: (1., 1.),
+            speed: (0., 0.),
+            direction: 0.,
+            spread: 0.,
+            gravity: (0., 0.),
+            linear_acceleration: (0., 0., 0., 0.),
+            rotation: (0., 0.),
+            spins: vec![0.],
+            sizes: vec![DEFAULT_SIZE],
+            colors: vec![Color::WHITE],
+        }
+    }
+
+    //=======================================================================
+    //                             SETTINGS
+    //=======================================================================
+    pub fn set_shape(&mut self, shape: ParticleShape) {
+        self.shape = shape;
+    }
+
+    pub fn set_position(&mut self, x: f32, y: f32) {
+        self.x = x;
+        self.y = y;
+    }
+
+    /*
+     * set_emission_area()
+     *
+     * @Brief : Particles are emitted in a rectangle centered on the position
+     */
+    pub fn set_emission_area(&mut self, width: f32, height: f32) {
+        self.area_width = width;
+        self.area_height = height;
+    }
+
+    /*
+     * set_emission_rate()
+     *
+     * @Brief : Particles emitted per second while the system is active
+     */
+    pub fn set_emission_rate(&mut self, rate: f32) {
+        self.emission_rate = rate.max(0.);
+    }
+
+    /*
+     * set_emitter_lifetime()
+     *
+     * @Brief : Seconds of emission after start(), negative = forever
+     */
+    pub fn set_emitter_lifetime(&mut self, lifetime: f32) {
+        self.emitter_lifetime = lifetime;
+    }
+
+    /*
+     * set_lifetime()
+     *
+     * @Brief : Seconds of life of a particle, random between min and max
+     */
+    pub fn set_lifetime(&mut self, min: f32, max: f32) {
+        self.lifetime = (min, max);
+    }
+
+    /*
+     * set_speed()
+     *
+     * @Brief : Initial speed in pixels per second, random between min and max
+     */
+    pub fn set_speed(&mut self, min: f32, max: f32) {
+        self.speed = (min, max);
+    }
+
+    /*
+     * set_direction()
+     *
+     * @Brief : Direction of emission in radians, spread is the total angle around it
+     */
+    pub fn set_direction(&mut self, direction: f32, spread: f32) {
+        self.direction = direction;
+        self.spread = spread;
+    }
+
+    /*
+     * set_gravity()
+     *
+     * @Brief : Acceleration applied to every particle
+     */
+    pub fn set_gravity(&mut self, x: f32, y: f32) {
+        self.gravity = (x, y);
+    }
+
+    /*
+     * set_linear_acceleration()
+     *
+     * @Brief : Acceleration of each particle, random between (min_x, min_y) and (max_x, max_y)
+     */
+    pub fn set_linear_acceleration(&mut self, min_x: f32, min_y: f32, max_x: f32, max_y: f32) {
+        self.linear_acceleration = (min_x, min_y, max_x, max_y);
+    }
+
+    /*
+     * set_rotation()
+     *
+     * @Brief : Initial rotation in radians, random between min and max
+     */
+    pub fn set_rotation(&mut self, min: f32, max: f32) {
+        self.rotation = (min, max);
+    }
+
+    /*
+     * set_spins()
+     *
+     * @Brief : Rotation speed (radians per second) over the life of the particles,
+     *          values are spread evenly from birth to death
+     */
+    pub fn set_spins(&mut self, spins: Vec<f32>) {
+        self.spins = if spins.is_empty() { vec![0.] } else { spins };
+    }
+
+    /*
+     * set_sizes()
+     *
+     * @Brief : Width in pixels over the life of the particles (DEFAULT_SIZE if empty)
+     */
+    pub fn set_sizes(&mut self, sizes: Vec<f32>) {
+        self.sizes = if sizes.is_empty() { vec![DEFAULT_SIZE] } else { sizes };
+    }
+
+    /*
+     * set_colors()
+     *
+     * @Brief : Color over the life of the particles
+     */
+    pub fn set_colors(&mut self, colors: Vec<Color>) {
+        self.colors = if colors.is_empty() { vec![Color::WHITE] } else { colors };
+    }
+
+    //=======================================================================
+    //                             EMISSION
+    //=======================================================================
+    pub fn start(&mut self) {
+        self.active = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.active = false;
+        self.emission_timer = 0.;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /*
+     * reset()
+     *
+     * @Brief : Remove all the particles
+     */
+    pub fn reset(&mut self) {
+        self.particles.clear();
+        self.emission_timer = 0.;
+    }
+
+    pub fn count(&self) -> usize {
+        self.particles.len()
+    }
+
+    /*
+     * emit()
+     *
+     * @Brief : Emit a burst of particles now (even if the system is stopped)
+     */
+    pub fn emit(&mut self, count: usize) {
+        for _ in 0..count {
+            if self.particles.len() >= self.max_particles {
+                break;
+            }
+
+            let lifetime = self.random.range(self.lifetime.0, self.lifetime.1);
+            let speed = self.random.range(self.speed.0, self.speed.1);
+            let direction = self.direction + self.random.range(-self.spread / 2., self.spread / 2.);
+            let (min_x, min_y, max_x, max_y) = self.linear_acceleration;
+
+            let particle = Particle {
+                x: self.x + self.random.range(-self.area_width / 2., self.area_width / 2.),
+                y: self.y + self.random.range(-self.area_height / 2., self.area_height / 2.),
+                vx: direction.cos() * speed,
+                vy: direction.sin() * speed,
+                ax: self.random.range(min_x, max_x),
+                ay: self.random.range(min_y, max_y),
+                rotation: self.random.range(self.rotation.0, self.rotation.1),
+                spin: 0.,
+                life: 0.,
+                lifetime,
+            };
+            self.particles.push(particle);
+        }
+    }
+
+    /*
+     * update()
+     *
+     * @Brief : Emit new particles and move the living ones
+     */
+    pub fn update(&mut self, dt: f32) {
+
+        // Emitter
+        if self.active {
+            if self.emission_rate > 0. {
+                self.emission_timer += dt;
+                let interval = 1. / self.emission_rate;
+                while self.emission_timer >= interval {
+                    self.emission_timer -= interval;
+                    self.emit(1);
+                }
+            }
+
+            if self.emitter_lifetime >= 0. {
+                self.emitter_lifetime -= dt;
+                if self.emitter_lifetime < 0. {
+                    self.stop();
+                }
+            }
+        }
+
+        // Particles
+        let (gx, gy) = self.gravity;
+        let spins = &self.spins;
+        self.particles.retain_mut(|p| {
+            p.life += dt;
+            if p.life >= p.lifetime {
+                return false;
+            }
+
+            p.vx += (p.ax + gx) * dt;
+            p.vy += (p.ay + gy) * dt;
+            p.x += p.vx * dt;
+            p.y += p.vy * dt;
+
+            p.spin = interpolate(spins, p.life / p.lifetime);
+            p.rotation += p.spin * dt;
+
+            true
+        });
+    }
+
+    //=======================================================================
+    //                             DRAW
+    //=======================================================================
+    /*
+     * draw()
+     *
+     * @Brief : Draw the particles with the blend mode of graphics
+     */
+    pub fn draw(&self, graphics: &mut Graphics) -> Gc2dResult<()> {
+        for p in self.particles.iter() {
+            let t = p.life / p.lifetime;
+            let size = interpolate(&self.sizes, t);
+            let color = interpolate_color(&self.colors, t);
+
+            match self.shape {
+                ParticleShape::Image(image, quad) => {
+                    let width = match quad {
+                        Some(quad) => quad.width,
+                        None => graphics.get_image_width(image)?,
+                    };
+                    let scale = if width > 0. { size / width } else { 0. };
+                    let params = DrawParams::new(p.x, p.y)
+                        .with_origin_normalized(0.5, 0.5)
+                        .with_rotation(p.rotation)
+                        .with_scale(scale, scale)
+                        .with_tint(color);
+                    graphics.draw_with(image, quad, &params)?;
+                },
+                ParticleShape::Rectangle => {
+                    graphics.rectangle(DrawMode::Fill, p.x - size / 2., p.y - size / 2., size, size, Some(color))?;
+                },
+                ParticleShape::Circle => {
+                    graphics.disk(p.x, p.y, size / 2., color)?;
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+// Value of a curve at t (0. = birth, 1. = death)
+fn curve_position(len: usize, t: f32) -> (usize, usize, f32) {
+    let position = t.clamp(0., 1.) * (len - 1) as f32;
+    let index = (position.floor() as usize).min(len - 1);
+    (index, (index + 1).min(len - 1), position - index as f32)
+}
+
+fn interpolate(values: &[f32], t: f32) -> f32 {
+    let (a, b, f) = curve_position(values.len(), t);
+    values[a] + (values[b] - values[a]) * f
+}
+
+fn interpolate_color(colors: &[Color], t: f32) -> Color {
+    let (a, b, f) = curve_position(colors.len(), t);
+    colors[a].lerp(colors[b], f)
+}
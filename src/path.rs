@@ -0,0 +1,650 @@
+use std::f32::consts::PI;
+
+/*================================================================
+ *                            P A T H
+ *================================================================*/
+/*
+ * LineJoin : Shape of the corners of a stroked line
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LineJoin {
+    // Sharp corner, beveled when it would be longer than 4 line widths
+    Miter,
+    Round,
+    Bevel,
+}
+
+/*
+ * LineCap : Shape of the ends of a stroked line
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LineCap {
+    // The line stops at its end points
+    Butt,
+    Round,
+    // The line goes half its width past its end points
+    Square,
+}
+
+// Miter length limit, in line widths
+const MITER_LIMIT: f32 = 4.;
+
+#[derive(Clone, Copy, Debug)]
+enum PathCommand {
+    MoveTo(f32, f32),
+    LineTo(f32, f32),
+    QuadTo(f32, f32, f32, f32),
+    CubicTo(f32, f32, f32, f32, f32, f32),
+    ArcTo(f32, f32, f32, f32, f32),
+    Close,
+}
+
+/*
+ * Path : Lines and curves, drawn with graphics.path()
+ *
+ *   let path = Path::new()
+ *       .move_to(10., 10.)
+ *       .quad_to(50., 0., 90., 10.)
+ *       .line_to(90., 50.)
+ *       .close()
+ *       .with_line_width(3.);
+ */
+#[derive(Clone, Debug)]
+pub struct Path {
+    commands: Vec<PathCommand>,
+    pub line_width: f32,
+    pub line_join: LineJoin,
+    pub line_cap: LineCap,
+}
+
+// A flattened part of a path, between two move_to()
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SubPath {
+    pub points: Vec<(f32, f32)>,
+    pub closed: bool,
+}
+
+impl Default for Path {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Path {
+    pub fn new() -> Self {
+        Self {
+            commands: Vec::new(),
+            line_width: 1.,
+            line_join: LineJoin::Miter,
+            line_cap: LineCap::Butt,
+        }
+    }
+
+    //=======================================================================
+    //                             BUILDER
+    //=======================================================================
+    /*
+     * move_to()
+     *
+     * @Brief : Start a new part of the path at (x, y)
+     */
+    pub fn move_to(mut self, x: f32, y: f32) -> Self {
+        self.commands.push(PathCommand::MoveTo(x, y));
+        self
+    }
+
+    /*
+     * line_to()
+     *
+     * @Brief : Straight line from the current point to (x, y)
+     */
+    pub fn line_to(mut self, x: f32, y: f32) -> Self {
+        self.commands.push(PathCommand::LineTo(x, y));
+        self
+    }
+
+    /*
+     * quad_to()
+     *
+     * @Brief : Quadratic Bezier curve to (x, y), bent toward (cx, cy)
+     */
+    pub fn quad_to(mut self, cx: f32, cy: f32, x: f32, y: f32) -> Self {
+        self.commands.push(PathCommand::QuadTo(cx, cy, x, y));
+        self
+    }
+
+    /*
+     * cubic_to()
+     *
+     * @Brief : Cubic Bezier curve to (x, y) with two control points
+     */
+    pub fn cubic_to(mut self, c1x: f32, c1y: f32, c2x: f32, c2y: f32, x: f32, y: f32) -> Self {
+        self.commands.push(PathCommand::CubicTo(c1x, c1y, c2x, c2y, x, y));
+        self
+    }
+
+    /*
+     * arc_to()
+     *
+     * @Brief : Rounded corner of radius between the lines current point -> (x1, y1)
+     *          and (x1, y1) -> (x2, y2), like the arcTo() of a HTML canvas
+     *          The path ends on the arc, call line_to(x2, y2) to reach the end
+     */
+    pub fn arc_to(mut self, x1: f32, y1: f32, x2: f32, y2: f32, radius: f32) -> Self {
+        self.commands.push(PathCommand::ArcTo(x1, y1, x2, y2, radius));
+        self
+    }
+
+    /*
+     * close()
+     *
+     * @Brief : Line back to the start of the current part
+     */
+    pub fn close(mut self) -> Self {
+        self.commands.push(PathCommand::Close);
+        self
+    }
+
+    pub fn with_line_width(mut self, width: f32) -> Self {
+        self.line_width = width.max(0.);
+        self
+    }
+
+    pub fn with_line_join(mut self, join: LineJoin) -> Self {
+        self.line_join = join;
+        self
+    }
+
+    pub fn with_line_cap(mut self, cap: LineCap) -> Self {
+        self.line_cap = cap;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    //=======================================================================
+    //                             FLATTEN
+    //=======================================================================
+    /*
+     * flatten()
+     *
+     * @Brief : The parts of the path as polylines, curves cut in short lines
+     */
+    pub(crate) fn flatten(&self) -> Vec<SubPath> {
+        let mut subpaths: Vec<SubPath> = Vec::new();
+        let mut current = SubPath::default();
+        let mut start = (0., 0.);
+
+        for command in self.commands.iter() {
+            // Lines without a move_to start where the last part started
+            if current.points.is_empty() && !matches!(command, PathCommand::MoveTo(..)) {
+                current.points.push(start);
+            }
+            let last = *current.points.last().unwrap_or(&start);
+
+            match *command {
+                PathCommand::MoveTo(x, y) => {
+                    if !current.points.is_empty() {
+                        subpaths.push(std::mem::take(&mut current));
+                    }
+                    start = (x, y);
+                    current.points.push(start);
+                },
+                PathCommand::LineTo(x, y) => {
+                    current.points.push((x, y));
+                },
+                PathCommand::QuadTo(cx, cy, x, y) => {
+                    let count = curve_segments(&[last, (cx, cy), (x, y)]);
+                    for i in 1..=count {
+                        let t = i as f32 / count as f32;
+                        let u = 1. - t;
+                        current.points.push((
+                            u * u * last.0 + 2. * u * t * cx + t * t * x,
+                            u * u * last.1 + 2. * u * t * cy + t * t * y,
+                        ));
+                    }
+                },
+                PathCommand::CubicTo(c1x, c1y, c2x, c2y, x, y) => {
+                    let count = curve_segments(&[last, (c1x, c1y), (c2x, c2y), (x, y)]);
+                    for i in 1..=count {
+                        let t = i as f32 / count as f32;
+                        let u = 1. - t;
+                        current.points.push((
+                            u * u * u * last.0 + 3. * u * u * t * c1x + 3. * u * t * t * c2x + t * t * t * x,
+                            u * u * u * last.1 + 3. * u * u * t * c1y + 3. * u * t * t * c2y + t * t * t * y,
+                        ));
+                    }
+                },
+                PathCommand::ArcTo(x1, y1, x2, y2, radius) => {
+                    arc_points(&mut current.points, last, (x1, y1), (x2, y2), radius);
+                },
+                PathCommand::Close => {
+                    current.closed = true;
+                    subpaths.push(std::mem::take(&mut current));
+                },
+            }
+        }
+
+        if !current.points.is_empty() {
+            subpaths.push(current);
+        }
+
+        // Consecutive equal points would give segments without direction
+        for subpath in subpaths.iter_mut() {
+            subpath.points.dedup_by(|a, b| (a.0 - b.0).abs() < 1e-4 && (a.1 - b.1).abs() < 1e-4);
+        }
+        subpaths
+    }
+}
+
+// Number of lines for a curve, from the length of its control polygon
+fn curve_segments(points: &[(f32, f32)]) -> usize {
+    let length: f32 = points.windows(2).map(|w| distance(w[0], w[1])).sum();
+    ((length / 4.).ceil() as usize).clamp(2, 100)
+}
+
+// Points of the arc of arc_to(), starting with a line to the first tangent point
+fn arc_points(points: &mut Vec<(f32, f32)>, p0: (f32, f32), p1: (f32, f32), p2: (f32, f32), radius: f32) {
+    let v0 = normalize((p0.0 - p1.0, p0.1 - p1.1));
+    let v2 = normalize((p2.0 - p1.0, p2.1 - p1.1));
+    let cross = v0.0 * v2.1 - v0.1 * v2.0;
+
+    // Degenerated corner : a straight line to (x1, y1)
+    if radius <= 0. || v0 == (0., 0.) || v2 == (0., 0.) || cross.abs() < 1e-6 {
+        points.push(p1);
+        return;
+    }
+
+    let angle = (v0.0 * v2.0 + v0.1 * v2.1).clamp(-1., 1.).acos();
+    let tangent = radius / (angle / 2.).tan();
+    let bisector = normalize((v0.0 + v2.0, v0.1 + v2.1));
+    let to_center = radius / (angle / 2.).sin();
+
+    let center = (p1.0 + bisector.0 * to_center, p1.1 + bisector.1 * to_center);
+    let t0 = (p1.0 + v0.0 * tangent, p1.1 + v0.1 * tangent);
+    let t2 = (p1.0 + v2.0 * tangent, p1.1 + v2.1 * tangent);
+
+    let start = (t0.1 - center.1).atan2(t0.0 - center.0);
+    let end = (t2.1 - center.1).atan2(t2.0 - center.0);
+    let mut sweep = end - start;
+    if sweep > PI {
+        sweep -= 2. * PI;
+    } else if sweep < -PI {
+        sweep += 2. * PI;
+    }
+
+    points.push(t0);
+    let count = ((sweep.abs() * radius / 4.).ceil() as usize).clamp(2, 100);
+    for i in 1..=count {
+        let a = start + sweep * i as f32 / count as f32;
+        points.push((center.0 + radius * a.cos(), center.1 + radius * a.sin()));
+    }
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+fn normalize(v: (f32, f32)) -> (f32, f32) {
+    let length = (v.0 * v.0 + v.1 * v.1).sqrt();
+    if length > 0. { (v.0 / length, v.1 / length) } else { (0., 0.) }
+}
+
+//=======================================================================
+//                             STROKE
+//=======================================================================
+/*
+ * stroke_polygons()
+ *
+ * @Brief : Convex polygons covering a polyline of width line_width
+ *          (one per segment, join and cap), to fill with union_spans()
+ */
+pub(crate) fn stroke_polygons(points: &[(f32, f32)], closed: bool, width: f32, join: LineJoin, cap: LineCap) -> Vec<Vec<(f32, f32)>> {
+    let mut polygons = Vec::new();
+    let half = width / 2.;
+
+    if points.is_empty() || half <= 0. {
+        return polygons;
+    }
+
+    // A lone point only shows its caps
+    if points.len() == 1 {
+        let (x, y) = points[0];
+        match cap {
+            LineCap::Butt => {},
+            LineCap::Round => polygons.push(circle_polygon(x, y, half)),
+            LineCap::Square => polygons.push(vec![(x - half, y - half), (x + half, y - half), (x + half, y + half), (x - half, y + half)]),
+        }
+        return polygons;
+    }
+
+    let mut points = points.to_vec();
+    if closed && points.len() > 2 && points[points.len() - 1] != points[0] {
+        points.push(points[0]);
+    }
+    let closed = closed && points.len() > 3;
+
+    // Square caps : the ends go half a width further
+    if !closed && cap == LineCap::Square {
+        let last = points.len() - 1;
+        let d = normalize((points[1].0 - points[0].0, points[1].1 - points[0].1));
+        points[0] = (points[0].0 - d.0 * half, points[0].1 - d.1 * half);
+        let d = normalize((points[last].0 - points[last - 1].0, points[last].1 - points[last - 1].1));
+        points[last] = (points[last].0 + d.0 * half, points[last].1 + d.1 * half);
+    }
+
+    // Segments
+    let mut directions = Vec::with_capacity(points.len() - 1);
+    for w in points.windows(2) {
+        let d = normalize((w[1].0 - w[0].0, w[1].1 - w[0].1));
+        let n = (-d.1 * half, d.0 * half);
+        polygons.push(vec![
+            (w[0].0 + n.0, w[0].1 + n.1),
+            (w[1].0 + n.0, w[1].1 + n.1),
+            (w[1].0 - n.0, w[1].1 - n.1),
+            (w[0].0 - n.0, w[0].1 - n.1),
+        ]);
+        directions.push(d);
+    }
+
+    // Joins, between each segment and the next one
+    let mut corners: Vec<(usize, usize, (f32, f32))> = (1..directions.len())
+        .map(|i| (i - 1, i, points[i]))
+        .collect();
+    if closed {
+        corners.push((directions.len() - 1, 0, points[0]));
+    }
+
+    for (before, after, vertex) in corners {
+        let (d0, d1) = (directions[before], directions[after]);
+        let cross = d0.0 * d1.1 - d0.1 * d1.0;
+        if cross.abs() < 1e-6 && d0.0 * d1.0 + d0.1 * d1.1 > 0. {
+            continue;
+        }
+
+        // Outer side of the turn
+        let side = if cross > 0. { -1. } else { 1. };
+        let n0 = (-d0.1 * half * side, d0.0 * half * side);
+        let n1 = (-d1.1 * half * side, d1.0 * half * side);
+        let a = (vertex.0 + n0.0, vertex.1 + n0.1);
+        let b = (vertex.0 + n1.0, vertex.1 + n1.1);
+
+        match join {
+            LineJoin::Round => polygons.push(circle_polygon(vertex.0, vertex.1, half)),
+            LineJoin::Bevel => polygons.push(vec![vertex, a, b]),
+            LineJoin::Miter => {
+                let m = normalize((n0.0 + n1.0, n0.1 + n1.1));
+                let cos = (m.0 * n0.0 + m.1 * n0.1) / half;
+                if cos > 1. / (2. * MITER_LIMIT) {
+                    let length = half / cos;
+                    polygons.push(vec![vertex, a, (vertex.0 + m.0 * length, vertex.1 + m.1 * length), b]);
+                } else {
+                    polygons.push(vec![vertex, a, b]);
+                }
+            },
+        }
+    }
+
+    // Round caps
+    if !closed && cap == LineCap::Round {
+        let (first, last) = (points[0], points[points.len() - 1]);
+        polygons.push(circle_polygon(first.0, first.1, half));
+        polygons.push(circle_polygon(last.0, last.1, half));
+    }
+
+    polygons
+}
+
+/*
+ * circle_polygon()
+ *
+ * @Brief : Points around a circle, close enough to look round
+ */
+pub(crate) fn circle_polygon(x: f32, y: f32, radius: f32) -> Vec<(f32, f32)> {
+    let count = ((2. * PI * radius / 3.).ceil() as usize).clamp(8, 128);
+    (0..count)
+        .map(|i| {
+            let a = 2. * PI * i as f32 / count as f32;
+            (x + radius * a.cos(), y + radius * a.sin())
+        })
+        .collect()
+}
+
+//=======================================================================
+//                             SPANS
+//=======================================================================
+/*
+ * Polygons are filled line by line : a span (y, x_start, x_end) is a part
+ * of a row of pixels inside the shape, sampled at the center of the pixels.
+ * Spans are clipped to the size (width, height) of the render target, so a
+ * shape far out of the screen costs nothing.
+ */
+pub(crate) type Span = (i32, i32, i32);
+
+// Bounds of the rows covered by polygons, in the clip height
+fn rows(polygons: &[Vec<(f32, f32)>], clip: (u32, u32)) -> std::ops::Range<i32> {
+    let points = polygons.iter().flatten();
+    let top = points.clone().map(|p| p.1).fold(f32::MAX, f32::min);
+    let bottom = points.map(|p| p.1).fold(f32::MIN, f32::max);
+    if top > bottom {
+        return 0..0;
+    }
+    // Clamped as floats : the casts saturate, they never overflow
+    let top = top.floor().max(0.) as i32;
+    let bottom = (bottom.ceil() + 1.).min(clip.1.min(i32::MAX as u32) as f32) as i32;
+    top..bottom.max(top)
+}
+
+// Parts of the row y inside the polygons, with the even-odd rule
+fn row_spans(polygons: &[Vec<(f32, f32)>], y: f32, spans: &mut Vec<(f32, f32)>) {
+    let mut crossings = Vec::new();
+    for polygon in polygons.iter() {
+        let mut j = polygon.len().wrapping_sub(1);
+        for i in 0..polygon.len() {
+            let (xi, yi) = polygon[i];
+            let (xj, yj) = polygon[j];
+            if (yi > y) != (yj > y) {
+                crossings.push(xi + (y - yi) * (xj - xi) / (yj - yi));
+            }
+            j = i;
+        }
+    }
+    crossings.sort_by(|a, b| a.total_cmp(b));
+    for pair in crossings.chunks_exact(2) {
+        spans.push((pair[0], pair[1]));
+    }
+}
+
+// Pixels whose center is in [x0, x1], in the clip width
+fn push_span(spans: &mut Vec<Span>, y: i32, x0: f32, x1: f32, clip: (u32, u32)) {
+    let start = (x0 - 0.5).ceil().max(0.) as i32;
+    let end = (x1 - 0.5).floor().min(clip.0.min(i32::MAX as u32) as f32 - 1.) as i32;
+    if end >= start {
+        spans.push((y, start, end));
+    }
+}
+
+/*
+ * fill_spans()
+ *
+ * @Brief : Spans inside the polygons, a polygon inside another one makes a hole
+ */
+pub(crate) fn fill_spans(polygons: &[Vec<(f32, f32)>], clip: (u32, u32)) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut row = Vec::new();
+    for y in rows(polygons, clip) {
+        row.clear();
+        row_spans(polygons, y as f32 + 0.5, &mut row);
+        for (x0, x1) in row.iter() {
+            push_span(&mut spans, y, *x0, *x1, clip);
+        }
+    }
+    spans
+}
+
+/*
+ * union_spans()
+ *
+ * @Brief : Spans inside any of the polygons, each pixel only once
+ *          (a translucent stroke is not darker where its parts overlap)
+ */
+pub(crate) fn union_spans(polygons: &[Vec<(f32, f32)>], clip: (u32, u32)) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut row = Vec::new();
+    for y in rows(polygons, clip) {
+        row.clear();
+        for polygon in polygons.iter() {
+            row_spans(std::slice::from_ref(polygon), y as f32 + 0.5, &mut row);
+        }
+        row.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut merged: Option<(f32, f32)> = None;
+        for (x0, x1) in row.iter().copied() {
+            merged = match merged {
+                Some((m0, m1)) if x0 <= m1 => Some((m0, m1.max(x1))),
+                Some((m0, m1)) => {
+                    push_span(&mut spans, y, m0, m1, clip);
+                    Some((x0, x1))
+                },
+                None => Some((x0, x1)),
+            };
+        }
+        if let Some((m0, m1)) = merged {
+            push_span(&mut spans, y, m0, m1, clip);
+        }
+    }
+    spans
+}
+
+//=======================================================================
+//                             DASHES
+//=======================================================================
+/*
+ * dash()
+ *
+ * @Brief : Cut a polyline in the dashes of a pattern (lengths of dash, gap,
+ *          dash, gap...), the pattern goes on around the corners
+ */
+pub(crate) fn dash(points: &[(f32, f32)], closed: bool, pattern: &[f32]) -> Vec<Vec<(f32, f32)>> {
+    let mut points = points.to_vec();
+    if closed && points.len() > 2 {
+        points.push(points[0]);
+    }
+
+    if pattern.iter().sum::<f32>() <= 0. || pattern.iter().any(|length| *length < 0.) {
+        return vec![points];
+    }
+
+    let mut dashes = Vec::new();
+    let mut current: Vec<(f32, f32)> = Vec::new();
+    let mut index = 0;
+    let mut left = pattern[0];
+
+    for w in points.windows(2) {
+        let (a, b) = (w[0], w[1]);
+        let length = distance(a, b);
+        let mut done = 0.;
+
+        while done < length {
+            let step = left.min(length - done);
+            let t0 = done / length;
+            let t1 = (done + step) / length;
+
+            // Even entries of the pattern are dashes
+            if index % 2 == 0 {
+                if current.is_empty() {
+                    current.push((a.0 + (b.0 - a.0) * t0, a.1 + (b.1 - a.1) * t0));
+                }
+                current.push((a.0 + (b.0 - a.0) * t1, a.1 + (b.1 - a.1) * t1));
+            }
+
+            done += step;
+            left -= step;
+            if left <= 0. {
+                if !current.is_empty() {
+                    dashes.push(std::mem::take(&mut current));
+                }
+                index = (index + 1) % pattern.len();
+                left = pattern[index];
+            }
+        }
+    }
+
+    if !current.is_empty() {
+        dashes.push(current);
+    }
+    dashes
+}
+
+/*
+ * stroke_edges()
+ *
+ * @Brief : The two sides of each segment of a polyline of width line_width
+ */
+pub(crate) fn stroke_edges(points: &[(f32, f32)], width: f32) -> Vec<((f32, f32), (f32, f32))> {
+    let half = width / 2.;
+    let mut edges = Vec::new();
+    for w in points.windows(2) {
+        let d = normalize((w[1].0 - w[0].0, w[1].1 - w[0].1));
+        let n = (-d.1 * half, d.0 * half);
+        edges.push(((w[0].0 + n.0, w[0].1 + n.1), (w[1].0 + n.0, w[1].1 + n.1)));
+        edges.push(((w[0].0 - n.0, w[0].1 - n.1), (w[1].0 - n.0, w[1].1 - n.1)));
+    }
+    edges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Left, top, right and bottom of the spans
+    fn bounds(spans: &[Span]) -> (i32, i32, i32, i32) {
+        spans.iter().fold((i32::MAX, i32::MAX, i32::MIN, i32::MIN), |(l, t, r, b), (y, x0, x1)| {
+            (l.min(*x0), t.min(*y), r.max(*x1), b.max(*y))
+        })
+    }
+
+    #[test]
+    fn dash_cuts_the_pattern_around_corners() {
+        let dashes = dash(&[(0., 0.), (10., 0.), (10., 10.)], false, &[4., 2.]);
+        assert_eq!(dashes.len(), 4);
+        assert_eq!(dashes[0], vec![(0., 0.), (4., 0.)]);
+        assert_eq!(dashes[1], vec![(6., 0.), (10., 0.)]);
+        // The third dash turns the corner
+        assert_eq!(dashes[2], vec![(10., 2.), (10., 6.)]);
+        assert_eq!(dashes[3], vec![(10., 8.), (10., 10.)]);
+    }
+
+    #[test]
+    fn dash_without_pattern_is_the_polyline() {
+        let points = [(0., 0.), (10., 0.), (10., 10.)];
+        assert_eq!(dash(&points, false, &[]), vec![points.to_vec()]);
+        assert_eq!(dash(&points, false, &[4., -1.]), vec![points.to_vec()]);
+        // Closed : back to the first point
+        assert_eq!(dash(&points, true, &[])[0].last(), Some(&(0., 0.)));
+    }
+
+    #[test]
+    fn stroke_butt_covers_the_segment() {
+        let polygons = stroke_polygons(&[(2., 10.), (12., 10.)], false, 4., LineJoin::Miter, LineCap::Butt);
+        let spans = union_spans(&polygons, (100, 100));
+        assert_eq!(bounds(&spans), (2, 8, 11, 11));
+    }
+
+    #[test]
+    fn stroke_square_caps_go_half_a_width_further() {
+        let polygons = stroke_polygons(&[(2., 10.), (12., 10.)], false, 4., LineJoin::Miter, LineCap::Square);
+        let spans = union_spans(&polygons, (100, 100));
+        assert_eq!(bounds(&spans), (0, 8, 13, 11));
+    }
+
+    #[test]
+    fn stroke_is_clipped() {
+        let polygons = stroke_polygons(&[(-20., 5.), (20., 5.)], false, 2., LineJoin::Miter, LineCap::Butt);
+        let spans = union_spans(&polygons, (10, 10));
+        assert_eq!(bounds(&spans), (0, 4, 9, 5));
+        assert!(stroke_polygons(&[(0., 0.), (5., 5.)], false, 0., LineJoin::Miter, LineCap::Butt).is_empty());
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::HashMap;
+
+use crate::{color::Color, fonts::FontStyle, gc2d::Gc2dResult, image::ImageHandle};
+
+/*================================================================
+ *                      R I C H   T E X T
+ *================================================================*/
+/*
+ * RichText : A text with inline markup, drawn with graphics.draw_rich_text()
+ *
+ *   [color=#ff0000]red[/color]   color in hex or a CSS name ([color=gold])
+ *   [b]bold[/b]  [i]italic[/i]   styles, they can be nested
+ *   [img=coin]                   an image added with with_image()
+ *   [[                           a literal '['
+ *
+ *   let text = RichText::new("You found 3 [img=coin] [color=gold][b]gold[/b][/color]!")
+ *       .with_image("coin", coin);
+ *   graphics.draw_rich_text(&text, 10., 10., Some(200.), None)?;
+ *
+ * The runs of a line share one baseline : images sit on it, a line with a
+ * tall image is taller. Lines are wrapped between words, a word wider than
+ * the wrap width is not cut. Unknown tags are printed as they are.
+ */
+#[derive(Clone, PartialEq, Debug)]
+enum Atom {
+    // None : the color given to draw_rich_text()
+    Word(String, FontStyle, Option<Color>),
+    Space,
+    Image(String),
+    Newline,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RichText {
+    atoms: Vec<Atom>,
+    images: HashMap<String, ImageHandle>,
+}
+
+impl RichText {
+    pub fn new(markup: &str) -> Self {
+        Self {
+            atoms: parse(markup),
+            images: HashMap::new(),
+        }
+    }
+
+    /*
+     * with_image()
+     *
+     * @Brief : Image drawn for the [img=name] tags
+     */
+    pub fn with_image(mut self, name: &str, image: ImageHandle) -> Self {
+        self.images.insert(name.to_string(), image);
+        self
+    }
+
+    /*
+     * get_plain_text()
+     *
+     * @Brief : The text without its markup (images are left out)
+     */
+    pub fn get_plain_text(&self) -> String {
+        self.atoms
+            .iter()
+            .map(|atom| match atom {
+                Atom::Word(word, _, _) => word.as_str(),
+                Atom::Space => " ",
+                Atom::Image(_) => "",
+                Atom::Newline => "\n",
+            })
+            .collect()
+    }
+
+    /*
+     * layout()
+     *
+     * @Brief : Place the runs of the text in lines, measure gives the width of a
+     *          string with a style, image_size the size of an image
+     */
+    pub(crate) fn layout<M, I>(&self, wrap_width: Option<f32>, metrics: LineMetrics, mut measure: M, mut image_size: I) -> Gc2dResult<RichLayout>
+    where
+        M: FnMut(&str, FontStyle) -> Gc2dResult<f32>,
+        I: FnMut(ImageHandle) -> Gc2dResult<(f32, f32)>,
+    {
+        let mut lines = Vec::new();
+        let mut line = LineBuilder::default();
+
+        for (group, spaced) in self.groups() {
+            let group = match group {
+                Some(group) => group,
+                None => {
+                    lines.push(line.finish(metrics));
+                    line = LineBuilder::default();
+                    continue;
+                },
+            };
+
+            // Size of the pieces of the word
+            let mut pieces = Vec::new();
+            for atom in group {
+                match atom {
+                    Atom::Word(word, style, color) => {
+                        let width = measure(word, *style)?;
+                        pieces.push(RichRun { x: 0., width, kind: RunKind::Text(word.clone(), *style, *color) });
+                    },
+                    Atom::Image(name) => {
+                        let image = *self
+                            .images
+                            .get(name)
+                            .ok_or_else(|| format!("Rich text: no image \"{}\"", name))?;
+                        let (width, height) = image_size(image)?;
+                        pieces.push(RichRun { x: 0., width, kind: RunKind::Image(image, height) });
+                    },
+                    Atom::Space | Atom::Newline => {},
+                }
+            }
+            let group_width: f32 = pieces.iter().map(|piece| piece.width).sum();
+            let space = if spaced && !line.runs.is_empty() { measure(" ", FontStyle::NORMAL)? } else { 0. };
+
+            // The word does not fit on the line : next line
+            if let Some(wrap_width) = wrap_width {
+                if !line.runs.is_empty() && line.width + space + group_width > wrap_width {
+                    lines.push(line.finish(metrics));
+                    line = LineBuilder::default();
+                }
+            }
+
+            let mut spaced = spaced && !line.runs.is_empty();
+            for piece in pieces {
+                line.push(piece, spaced, &mut measure)?;
+                spaced = false;
+            }
+        }
+        lines.push(line.finish(metrics));
+
+        Ok(RichLayout {
+            width: lines.iter().fold(0., |width: f32, line| width.max(line.width)),
+            height: lines.iter().map(|line| line.height).sum(),
+            lines,
+        })
+    }
+
+    // Words (atoms not separated by a space), whether a space is before them,
+    // None for a newline
+    fn groups(&self) -> Vec<(Option<&[Atom]>, bool)> {
+        let mut groups = Vec::new();
+        let mut start = 0;
+        let mut spaced = false;
+
+        for (i, atom) in self.atoms.iter().enumerate() {
+            if matches!(atom, Atom::Space | Atom::Newline) {
+                if start < i {
+                    groups.push((Some(&self.atoms[start..i]), spaced));
+                }
+                match atom {
+                    Atom::Newline => {
+                        groups.push((None, false));
+                        spaced = false;
+                    },
+                    _ => spaced = true,
+                }
+                start = i + 1;
+            }
+        }
+        if start < self.atoms.len() {
+            groups.push((Some(&self.atoms[start..]), spaced));
+        }
+        groups
+    }
+}
+
+//=======================================================================
+//                             PARSING
+//=======================================================================
+fn parse(markup: &str) -> Vec<Atom> {
+    let mut atoms = Vec::new();
+    let mut word = String::new();
+    let mut bold = 0;
+    let mut italic = 0;
+    let mut colors: Vec<Color> = Vec::new();
+
+    let mut rest = markup;
+    while let Some(c) = rest.chars().next() {
+        rest = &rest[c.len_utf8()..];
+
+        let style = font_style(bold, italic);
+        let flush = |word: &mut String, atoms: &mut Vec<Atom>| {
+            if !word.is_empty() {
+                atoms.push(Atom::Word(std::mem::take(word), style, colors.last().copied()));
+            }
+        };
+
+        match c {
+            '\n' => {
+                flush(&mut word, &mut atoms);
+                atoms.push(Atom::Newline);
+            },
+            '\r' => {},
+            c if c.is_whitespace() => {
+                flush(&mut word, &mut atoms);
+                if atoms.last() != Some(&Atom::Space) {
+                    atoms.push(Atom::Space);
+                }
+            },
+            '[' if rest.starts_with('[') => {
+                word.push('[');
+                rest = &rest[1..];
+            },
+            '[' => {
+                let tag = match rest.split_once(']') {
+                    Some((tag, _)) => tag,
+                    None => {
+                        word.push('[');
+                        continue;
+                    },
+                };
+
+                // Tags cut the word, the parts keep their own style
+                let known = match tag {
+                    "b" | "/b" | "i" | "/i" | "/color" => true,
+                    _ => tag.strip_prefix("color=").and_then(parse_color).is_some() || tag.starts_with("img="),
+                };
+                if !known {
+                    word.push('[');
+                    continue;
+                }
+                flush(&mut word, &mut atoms);
+
+                match tag {
+                    "b" => bold += 1,
+                    "/b" => bold = (bold - 1).max(0),
+                    "i" => italic += 1,
+                    "/i" => italic = (italic - 1).max(0),
+                    "/color" => { colors.pop(); },
+                    _ => {
+                        if let Some(name) = tag.strip_prefix("img=") {
+                            atoms.push(Atom::Image(name.trim().to_string()));
+                        } else if let Some(color) = tag.strip_prefix("color=").and_then(parse_color) {
+                            colors.push(color);
+                        }
+                    },
+                }
+                rest = &rest[tag.len() + 1..];
+            },
+            c => word.push(c),
+        }
+    }
+
+    if !word.is_empty() {
+        atoms.push(Atom::Word(word, font_style(bold, italic), colors.last().copied()));
+    }
+    atoms
+}
+
+fn font_style(bold: i32, italic: i32) -> FontStyle {
+    let mut style = FontStyle::NORMAL;
+    if bold > 0 {
+        style |= FontStyle::BOLD;
+    }
+    if italic > 0 {
+        style |= FontStyle::ITALIC;
+    }
+    style
+}
+
+// "#rrggbb" (or any Color::from_hex format) or a CSS color name
+fn parse_color(value: &str) -> Option<Color> {
+    let value = value.trim();
+    if value.starts_with('#') {
+        Color::from_hex(value).ok()
+    } else {
+        Color::from_name(value)
+    }
+}
+
+//=======================================================================
+//                             LAYOUT
+//=======================================================================
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct LineMetrics {
+    // Top of a line to the baseline of the font
+    pub ascent: f32,
+    pub line_height: f32,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum RunKind {
+    // None : the default color
+    Text(String, FontStyle, Option<Color>),
+    // Height of the image
+    Image(ImageHandle, f32),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct RichRun {
+    // From the left of the line
+    pub x: f32,
+    pub width: f32,
+    pub kind: RunKind,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct RichLine {
+    pub runs: Vec<RichRun>,
+    pub width: f32,
+    pub height: f32,
+    // Top of the line to the baseline
+    pub baseline: f32,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct RichLayout {
+    pub lines: Vec<RichLine>,
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Default)]
+struct LineBuilder {
+    runs: Vec<RichRun>,
+    width: f32,
+}
+
+impl LineBuilder {
+    // Add a run at the end of the line, merged with the last run when they
+    // have the same style and color, so that they are drawn at once
+    fn push<M: FnMut(&str, FontStyle) -> Gc2dResult<f32>>(&mut self, mut run: RichRun, spaced: bool, measure: &mut M) -> Gc2dResult<()> {
+        if let (Some(last), RunKind::Text(text, style, color)) = (self.runs.last_mut(), &run.kind) {
+            if let RunKind::Text(last_text, last_style, last_color) = &mut last.kind {
+                if last_style == style && last_color == color {
+                    if spaced {
+                        last_text.push(' ');
+                    }
+                    last_text.push_str(text);
+                    last.width = measure(last_text, *style)?;
+                    self.width = last.x + last.width;
+                    return Ok(());
+                }
+            }
+        }
+
+        if spaced {
+            self.width += measure(" ", FontStyle::NORMAL)?;
+        }
+        run.x = self.width;
+        self.width += run.width;
+        self.runs.push(run);
+        Ok(())
+    }
+
+    fn finish(self, metrics: LineMetrics) -> RichLine {
+        let baseline = self.runs.iter().fold(metrics.ascent, |baseline, run| match run.kind {
+            RunKind::Image(_, height) => baseline.max(height),
+            RunKind::Text(..) => baseline,
+        });
+        RichLine {
+            runs: self.runs,
+            width: self.width,
+            // The descent of the font stays under the baseline
+            height: baseline + (metrics.line_height - metrics.ascent).max(0.),
+            baseline,
+        }
+    }
+}
@@ -0,0 +1,378 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use serde::Deserialize;
+
+use crate::{gc2d::Gc2dResult, graphics::Graphics, image::{ImageHandle, Quad}};
+
+/*================================================================
+ *                    S P R I T E   S H E E T
+ *================================================================*/
+/*
+ * SpriteFrame : A named part of the sprite sheet image
+ */
+#[derive(Clone, Debug)]
+pub struct SpriteFrame {
+    pub name: String,
+    pub quad: Quad,
+    // Offset of the trimmed frame inside its original size
+    pub offset_x: f32,
+    pub offset_y: f32,
+    // Duration in seconds (only set by atlases which export it, like Aseprite)
+    pub duration: Option<f32>,
+}
+
+/*
+ * TagDirection : How an Aseprite tag plays its frames
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TagDirection {
+    Forward,
+    Reverse,
+    PingPong,
+    PingPongReverse,
+}
+
+/*
+ * SpriteTag : A named range of frames (Aseprite "frameTags")
+ */
+#[derive(Clone, Debug)]
+pub struct SpriteTag {
+    pub name: String,
+    pub from: usize,
+    pub to: usize,
+    pub direction: TagDirection,
+}
+
+pub struct SpriteSheet {
+    pub image: ImageHandle,
+    frames: Vec<SpriteFrame>,
+    names: HashMap<String, usize>,
+    tags: Vec<SpriteTag>,
+}
+
+impl SpriteSheet {
+    /*
+     * new()
+     *
+     * @Brief : Create an empty sprite sheet for an image
+     */
+    pub fn new(image: ImageHandle) -> Self {
+        Self {
+            image,
+            frames: Vec::new(),
+            names: HashMap::new(),
+            tags: Vec::new(),
+        }
+    }
+
+    /*
+     * add_frame()
+     *
+     * @Brief : Add a frame, a frame with the same name is replaced
+     */
+    pub fn add_frame(&mut self, frame: SpriteFrame) {
+        if let Some(index) = self.names.get(&frame.name) {
+            self.frames[*index] = frame;
+        } else {
+            self.names.insert(frame.name.clone(), self.frames.len());
+            self.frames.push(frame);
+        }
+    }
+
+    /*
+     * from_grid()
+     *
+     * @Brief : Cut the image in frames of frame_width x frame_height
+     *          margin  : pixels around the grid
+     *          spacing : pixels between two frames
+     *          Frames are named by their index ("0", "1", ...), row by row
+     */
+    pub fn from_grid(graphics: &Graphics, image: ImageHandle, frame_width: f32, frame_height: f32, margin: f32, spacing: f32) -> Gc2dResult<Self> {
+
+        if !frame_width.is_finite() || !frame_height.is_finite() || frame_width <= 0. || frame_height <= 0. {
+            return Err(format!("Invalid frame size {}x{}", frame_width, frame_height));
+        }
+        if !margin.is_finite() || margin < 0. {
+            return Err(format!("Invalid margin {}", margin));
+        }
+        if !spacing.is_finite() || spacing < 0. {
+            return Err(format!("Invalid spacing {}", spacing));
+        }
+
+        let image_width = graphics.get_image_width(image)?;
+        let image_height = graphics.get_image_height(image)?;
+
+        let mut sheet = SpriteSheet::new(image);
+
+        let mut y = margin;
+        while y + frame_height <= image_height - margin {
+            let mut x = margin;
+            while x + frame_width <= image_width - margin {
+                sheet.add_frame(SpriteFrame {
+                    name: sheet.frames.len().to_string(),
+                    quad: Quad { x, y, width: frame_width, height: frame_height },
+                    offset_x: 0.,
+                    offset_y: 0.,
+                    duration: None,
+                });
+                x += frame_width + spacing;
+            }
+            y += frame_height + spacing;
+        }
+
+        Ok(sheet)
+    }
+
+    /*
+     * from_atlas()
+     *
+     * @Brief : Load a JSON atlas exported by TexturePacker or Aseprite
+     *          ("Hash" or "Array" format). The atlas image is loaded in
+     *          the images manager, relative to the JSON file.
+     */
+    pub fn from_atlas(graphics: &mut Graphics, filename: &str) -> Gc2dResult<Self> {
+        let json = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        let atlas: JsonAtlas = serde_json::from_str(&json).map_err(|e| format!("{}: {}", filename, e))?;
+
+        let image_path = Path::new(filename)
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join(&atlas.meta.image);
+        let image = graphics.new_image(&image_path.to_string_lossy())?;
+
+        let (frames, tags) = atlas_frames(atlas, filename)?;
+
+        let mut sheet = SpriteSheet::new(image);
+        for frame in frames {
+            sheet.add_frame(frame);
+        }
+        sheet.tags = tags;
+        Ok(sheet)
+    }
+
+    /*
+     * get_frame()
+     */
+    pub fn get_frame(&self, name: &str) -> Option<&SpriteFrame> {
+        self.names.get(name).map(|index| &self.frames[*index])
+    }
+
+    /*
+     * get_quad()
+     *
+     * @Brief : Quad of a frame, to use with Graphics::draw
+     */
+    pub fn get_quad(&self, name: &str) -> Option<Quad> {
+        self.get_frame(name).map(|frame| frame.quad)
+    }
+
+    /*
+     * get_quad_at()
+     *
+     * @Brief : Quad of the frame at index (order of the grid or of the atlas)
+     */
+    pub fn get_quad_at(&self, index: usize) -> Option<Quad> {
+        self.frames.get(index).map(|frame| frame.quad)
+    }
+
+    pub fn get_frames(&self) -> &[SpriteFrame] {
+        &self.frames
+    }
+
+    /*
+     * get_tag()
+     */
+    pub fn get_tag(&self, name: &str) -> Option<&SpriteTag> {
+        self.tags.iter().find(|tag| tag.name == name)
+    }
+
+    pub fn get_tags(&self) -> &[SpriteTag] {
+        &self.tags
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+// Frames and tags of an atlas, in the order of the file
+fn atlas_frames(atlas: JsonAtlas, filename: &str) -> Gc2dResult<(Vec<SpriteFrame>, Vec<SpriteTag>)> {
+    let frames = match atlas.frames {
+        JsonFrames::Array(frames) => frames,
+        JsonFrames::Hash(frames) => {
+            // serde_json keeps the order of the file ("preserve_order")
+            let mut list = Vec::with_capacity(frames.len());
+            for (name, value) in frames {
+                let mut frame: JsonFrame = serde_json::from_value(value).map_err(|e| format!("{}: {}", filename, e))?;
+                frame.filename = Some(name);
+                list.push(frame);
+            }
+            list
+        },
+    };
+
+    let mut sprite_frames = Vec::with_capacity(frames.len());
+    for frame in frames {
+        let name = frame.filename.unwrap_or_else(|| sprite_frames.len().to_string());
+
+        if frame.rotated {
+            return Err(format!("{}: rotated frame \"{}\" is not supported", filename, name));
+        }
+
+        let (offset_x, offset_y) = frame.sprite_source_size
+            .map(|r| (r.x, r.y))
+            .unwrap_or((0., 0.));
+
+        sprite_frames.push(SpriteFrame {
+            name,
+            quad: Quad { x: frame.frame.x, y: frame.frame.y, width: frame.frame.w, height: frame.frame.h },
+            offset_x,
+            offset_y,
+            duration: frame.duration.map(|ms| ms / 1000.),
+        });
+    }
+
+    let mut tags = Vec::with_capacity(atlas.meta.frame_tags.len());
+    for tag in atlas.meta.frame_tags {
+        if tag.from > tag.to || tag.to >= sprite_frames.len() {
+            return Err(format!("{}: tag \"{}\" is out of the frames", filename, tag.name));
+        }
+
+        let direction = match tag.direction.as_str() {
+            "reverse" => TagDirection::Reverse,
+            "pingpong" => TagDirection::PingPong,
+            "pingpong_reverse" => TagDirection::PingPongReverse,
+            _ => TagDirection::Forward,
+        };
+
+        tags.push(SpriteTag {
+            name: tag.name,
+            from: tag.from,
+            to: tag.to,
+            direction,
+        });
+    }
+
+    Ok((sprite_frames, tags))
+}
+
+//=======================================================================
+//                          JSON ATLAS FORMAT
+//=======================================================================
+#[derive(Deserialize)]
+struct JsonAtlas {
+    frames: JsonFrames,
+    meta: JsonMeta,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonFrames {
+    Array(Vec<JsonFrame>),
+    Hash(serde_json::Map<String, serde_json::Value>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonFrame {
+    filename: Option<String>,
+    frame: JsonRect,
+    #[serde(default)]
+    rotated: bool,
+    sprite_source_size: Option<JsonRect>,
+    duration: Option<f32>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+struct JsonRect {
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonMeta {
+    image: String,
+    #[serde(default)]
+    frame_tags: Vec<JsonTag>,
+}
+
+#[derive(Deserialize)]
+struct JsonTag {
+    name: String,
+    from: usize,
+    to: usize,
+    #[serde(default)]
+    direction: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> Gc2dResult<(Vec<SpriteFrame>, Vec<SpriteTag>)> {
+        let atlas: JsonAtlas = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        atlas_frames(atlas, "test.json")
+    }
+
+    #[test]
+    fn hash_atlas_keeps_the_order_of_the_file() {
+        let (frames, tags) = parse(r#"{
+            "frames": {
+                "walk 1": { "frame": { "x": 32, "y": 0, "w": 16, "h": 24 }, "duration": 100 },
+                "walk 0": {
+                    "frame": { "x": 0, "y": 0, "w": 16, "h": 24 },
+                    "spriteSourceSize": { "x": 2, "y": 3, "w": 16, "h": 24 }
+                }
+            },
+            "meta": {
+                "image": "hero.png",
+                "frameTags": [{ "name": "walk", "from": 0, "to": 1, "direction": "pingpong" }]
+            }
+        }"#).unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].name, "walk 1");
+        assert_eq!(frames[0].quad.x, 32.);
+        assert_eq!(frames[0].duration, Some(0.1));
+        assert_eq!((frames[1].offset_x, frames[1].offset_y), (2., 3.));
+        assert_eq!(frames[1].duration, None);
+        assert_eq!(tags[0].direction, TagDirection::PingPong);
+    }
+
+    #[test]
+    fn array_atlas_names_frames_without_filename_by_index() {
+        let (frames, tags) = parse(r#"{
+            "frames": [
+                { "filename": "idle", "frame": { "x": 0, "y": 0, "w": 8, "h": 8 } },
+                { "frame": { "x": 8, "y": 0, "w": 8, "h": 8 } }
+            ],
+            "meta": { "image": "hero.png" }
+        }"#).unwrap();
+
+        assert_eq!(frames[0].name, "idle");
+        assert_eq!(frames[1].name, "1");
+        assert!(tags.is_empty());
+    }
+
+    #[test]
+    fn atlas_errors() {
+        let rotated = r#"{
+            "frames": [{ "frame": { "x": 0, "y": 0, "w": 8, "h": 8 }, "rotated": true }],
+            "meta": { "image": "hero.png" }
+        }"#;
+        assert!(parse(rotated).is_err());
+
+        let tag_out_of_frames = r#"{
+            "frames": [{ "frame": { "x": 0, "y": 0, "w": 8, "h": 8 } }],
+            "meta": { "image": "hero.png", "frameTags": [{ "name": "run", "from": 0, "to": 1 }] }
+        }"#;
+        assert!(parse(tag_out_of_frames).is_err());
+    }
+}
@@ -0,0 +1,375 @@
+use std::{collections::HashMap, rc::{Rc, Weak}, sync::atomic::{AtomicU64, Ordering}};
+
+use crate::{color::Color, fonts::{Font, FontStyle, FontsManager}, gc2d::Gc2dResult, graphics::FontsCreator, image::Image, texture_cache::destroy_texture};
+
+// Textures of printed texts kept at most (graphics.print())
+pub(crate) const TEXT_CACHE_SIZE: usize = 64;
+
+/*================================================================
+ *                            T E X T
+ *================================================================*/
+/*
+ * Text : A string rendered once in a texture, drawn with graphics.draw_text()
+ *        It is rendered again only when its string, font or color changes.
+ *        The texture belongs to graphics, it is freed on the frame after the
+ *        text is dropped.
+ */
+pub struct Text {
+    string: String,
+    // None : the font of graphics when drawn
+    font: Option<Font>,
+    color: Color,
+    // Key of the texture in the texts manager of graphics
+    id: u64,
+    // Alive as long as the text, the texts manager keeps a weak reference
+    owner: Rc<()>,
+    // Changed since the last rendering
+    changed: bool,
+    size: Option<(f32, f32)>,
+}
+
+// Ids of the texts, unique for the program
+static NEXT_TEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+impl Text {
+    pub fn new(string: &str, color: Color) -> Self {
+        Self {
+            string: string.to_string(),
+            font: None,
+            color,
+            id: NEXT_TEXT_ID.fetch_add(1, Ordering::Relaxed),
+            owner: Rc::new(()),
+            changed: true,
+            size: None,
+        }
+    }
+
+    pub fn with_font(mut self, font: Font) -> Self {
+        self.font = Some(font);
+        self
+    }
+
+    pub fn set_text(&mut self, string: &str) {
+        if self.string != string {
+            self.string = string.to_string();
+            self.clear();
+        }
+    }
+
+    pub fn get_text(&self) -> &str {
+        &self.string
+    }
+
+    pub fn set_font(&mut self, font: Option<Font>) {
+        if self.font != font {
+            self.font = font;
+            self.clear();
+        }
+    }
+
+    pub fn get_font(&self) -> Option<&Font> {
+        self.font.as_ref()
+    }
+
+    pub fn set_color(&mut self, color: Color) {
+        if self.color != color {
+            self.color = color;
+            self.clear();
+        }
+    }
+
+    pub fn get_color(&self) -> Color {
+        self.color
+    }
+
+    /*
+     * get_width()
+     *
+     * @Brief : Width of the last rendering (0. before the first draw)
+     */
+    pub fn get_width(&self) -> f32 {
+        self.size.map_or(0., |(width, _)| width)
+    }
+
+    pub fn get_height(&self) -> f32 {
+        self.size.map_or(0., |(_, height)| height)
+    }
+
+    // Rendered again by the next draw
+    fn clear(&mut self) {
+        self.changed = true;
+        self.size = None;
+    }
+}
+
+/*
+ * TextRendering : A texture of a text, (text id, generation). A text
+ *                 rendered again gets a new generation, the draws queued
+ *                 before keep the texture of their generation.
+ */
+pub(crate) type TextRendering = (u64, u64);
+
+struct TextEntry {
+    owner: Weak<()>,
+    font: Font,
+    generation: u64,
+}
+
+/*
+ * TextsManager : Textures of the Text objects, owned by graphics
+ */
+pub(crate) struct TextsManager {
+    entries: HashMap<u64, TextEntry>,
+    images: HashMap<TextRendering, Image>,
+    // Renderings replaced during the frame, freed by next_frame()
+    retired: Vec<TextRendering>,
+    generation: u64,
+}
+
+impl TextsManager {
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            images: HashMap::new(),
+            retired: Vec::new(),
+            generation: 0,
+        }
+    }
+
+    /*
+     * next_frame()
+     *
+     * @Brief : Free the replaced renderings and the textures of the dropped texts
+     */
+    pub fn next_frame(&mut self) {
+        let mut freed: Vec<TextRendering> = self.retired.drain(..).collect();
+        self.entries.retain(|id, entry| {
+            let alive = entry.owner.strong_count() > 0;
+            if !alive {
+                freed.push((*id, entry.generation));
+            }
+            alive
+        });
+
+        for rendering in freed {
+            if let Some(image) = self.images.remove(&rendering) {
+                destroy_texture(image.texture);
+            }
+        }
+    }
+
+    /*
+     * render()
+     *
+     * @Brief : Texture of a text with font, rendered again if something
+     *          changed since the last call. None for an empty text.
+     */
+    pub fn render(&mut self, text: &mut Text, font: &Font, fonts: &mut FontsManager, creator: &FontsCreator) -> Gc2dResult<Option<TextRendering>> {
+        if let Some(entry) = self.entries.get(&text.id) {
+            if !text.changed && entry.font == *font {
+                return Ok(Some((text.id, entry.generation)));
+            }
+        }
+
+        if let Some(entry) = self.entries.remove(&text.id) {
+            self.retired.push((text.id, entry.generation));
+        }
+        text.changed = false;
+        text.size = None;
+
+        if text.string.is_empty() {
+            return Ok(None);
+        }
+
+        let image = Image::from_texture(fonts.get_texture(creator, font, &text.string, text.color, FontStyle::NORMAL)?);
+        text.size = Some((image.width, image.height));
+
+        self.generation += 1;
+        let rendering = (text.id, self.generation);
+        self.images.insert(rendering, image);
+        self.entries.insert(text.id, TextEntry {
+            owner: Rc::downgrade(&text.owner),
+            font: font.clone(),
+            generation: self.generation,
+        });
+        Ok(Some(rendering))
+    }
+
+    /*
+     * take()
+     *
+     * @Brief : Image of a rendering, to give back with put()
+     */
+    pub fn take(&mut self, rendering: TextRendering) -> Option<Image> {
+        self.images.remove(&rendering)
+    }
+
+    pub fn put(&mut self, rendering: TextRendering, image: Image) {
+        self.images.insert(rendering, image);
+    }
+}
+
+/*================================================================
+ *                          S H A D O W
+ *================================================================*/
+/*
+ * TextShadow : Copy of a text drawn under it, moved by an offset,
+ *              printed by graphics.print_shadow()
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TextShadow {
+    pub offset_x: f32,
+    pub offset_y: f32,
+    pub color: Color,
+}
+
+impl TextShadow {
+    pub fn new(offset_x: f32, offset_y: f32, color: Color) -> Self {
+        Self { offset_x, offset_y, color }
+    }
+}
+
+/*================================================================
+ *                         W R A P P I N G
+ *================================================================*/
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+    // Lines stretched to the wrap width, except the last line of a paragraph
+    Justify,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct WrappedLine {
+    pub text: String,
+    pub width: f32,
+    // Last line before a newline or the end of the text
+    pub ends_paragraph: bool,
+}
+
+/*
+ * WrappedText : Lines of a text cut at a width, from graphics.get_wrapped_text()
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct WrappedText {
+    pub lines: Vec<WrappedLine>,
+    pub line_height: f32,
+    pub height: f32,
+}
+
+/*
+ * wrap()
+ *
+ * @Brief : Cut a text in lines no wider than wrap_width, at the newlines and
+ *          between words (a word too long is cut between letters)
+ */
+pub(crate) fn wrap<F: FnMut(&str) -> Gc2dResult<f32>>(text: &str, wrap_width: f32, mut measure: F) -> Gc2dResult<Vec<WrappedLine>> {
+    let mut lines = Vec::new();
+    let space = measure(" ")?;
+
+    for paragraph in text.split('\n') {
+        let paragraph = paragraph.trim_end_matches('\r');
+        let mut line = String::new();
+        let mut width = 0.;
+
+        for word in paragraph.split_whitespace() {
+            let mut word = word.to_string();
+            let mut word_width = measure(&word)?;
+
+            // The word does not fit on the line : next line
+            if !line.is_empty() && width + space + word_width > wrap_width {
+                lines.push(WrappedLine { text: std::mem::take(&mut line), width, ends_paragraph: false });
+                width = 0.;
+            }
+
+            // The word does not fit on a line : cut it
+            while line.is_empty() && word_width > wrap_width && word.chars().count() > 1 {
+                let mut cut = String::new();
+                let mut cut_width = 0.;
+                for c in word.chars() {
+                    let next = format!("{}{}", cut, c);
+                    let next_width = measure(&next)?;
+                    if next_width > wrap_width && !cut.is_empty() {
+                        break;
+                    }
+                    cut = next;
+                    cut_width = next_width;
+                }
+                word = word[cut.len()..].to_string();
+                if word.is_empty() {
+                    word = cut;
+                    word_width = cut_width;
+                    break;
+                }
+                lines.push(WrappedLine { text: cut, width: cut_width, ends_paragraph: false });
+                word_width = measure(&word)?;
+            }
+
+            if line.is_empty() {
+                width = word_width;
+            } else {
+                line.push(' ');
+                width += space + word_width;
+            }
+            line.push_str(&word);
+        }
+
+        lines.push(WrappedLine { text: line, width, ends_paragraph: true });
+    }
+
+    Ok(lines)
+}
+
+/*================================================================
+ *                      T E X T   C A C H E
+ *================================================================*/
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub(crate) struct TextKey {
+    pub font: Font,
+    pub string: String,
+    pub color: Color,
+    pub style: FontStyle,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every character is 10 pixels wide
+    fn measure(text: &str) -> Gc2dResult<f32> {
+        Ok(text.chars().count() as f32 * 10.)
+    }
+
+    fn texts(lines: &[WrappedLine]) -> Vec<&str> {
+        lines.iter().map(|line| line.text.as_str()).collect()
+    }
+
+    #[test]
+    fn wrap_between_words() {
+        let lines = wrap("the quick brown fox", 100., measure).unwrap();
+        assert_eq!(texts(&lines), ["the quick", "brown fox"]);
+        assert_eq!(lines[0].width, 90.);
+        assert!(!lines[0].ends_paragraph);
+        assert!(lines[1].ends_paragraph);
+    }
+
+    #[test]
+    fn wrap_keeps_newlines_and_empty_lines() {
+        let lines = wrap("one\r\n\ntwo", 100., measure).unwrap();
+        assert_eq!(texts(&lines), ["one", "", "two"]);
+        assert!(lines.iter().all(|line| line.ends_paragraph));
+    }
+
+    #[test]
+    fn wrap_cuts_a_word_wider_than_a_line() {
+        let lines = wrap("abcdefghij xy", 40., measure).unwrap();
+        assert_eq!(texts(&lines), ["abcd", "efgh", "ij", "xy"]);
+    }
+
+    #[test]
+    fn wrap_forwards_measure_errors() {
+        assert!(wrap("text", 100., |_| Err(String::from("no font"))).is_err());
+    }
+}
@@ -0,0 +1,88 @@
+use sdl2::render::Texture;
+
+// Frames a cached texture stays in memory without being drawn
+const CACHE_FRAMES: u64 = 120;
+
+/*
+ * destroy_texture()
+ *
+ * @Brief : Free a texture, they are not freed on drop ("unsafe_textures")
+ */
+pub(crate) fn destroy_texture(texture: Texture) {
+    unsafe { texture.destroy() };
+}
+
+/*================================================================
+ *                   T E X T U R E   C A C H E
+ *================================================================*/
+/*
+ * TextureCache : Textures drawn lately by key, so that what is drawn every
+ *                frame (a text, a gradient) is rendered once.
+ *                At most size textures, the least recently drawn is freed first.
+ *
+ *   let texture = match cache.take(&key) {
+ *       Some(texture) => texture,
+ *       None => render()?,
+ *   };
+ *   // draw the texture
+ *   cache.put(key, texture);
+ */
+pub(crate) struct TextureCache<K: PartialEq> {
+    // Few entries : searched in order
+    entries: Vec<(K, Texture, u64)>,
+    size: usize,
+    frame: u64,
+}
+
+impl<K: PartialEq> TextureCache<K> {
+    pub fn new(size: usize) -> Self {
+        Self {
+            entries: Vec::new(),
+            size: size.max(1),
+            frame: 0,
+        }
+    }
+
+    /*
+     * next_frame()
+     *
+     * @Brief : Free the textures not drawn for a while
+     */
+    pub fn next_frame(&mut self) {
+        self.frame += 1;
+        let (expired, entries): (Vec<_>, Vec<_>) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(|(_, _, last_used)| last_used + CACHE_FRAMES < self.frame);
+        self.entries = entries;
+
+        for (_, texture, _) in expired {
+            destroy_texture(texture);
+        }
+    }
+
+    /*
+     * take()
+     *
+     * @Brief : Texture of a key if it is cached, to give back with put()
+     */
+    pub fn take(&mut self, key: &K) -> Option<Texture> {
+        let index = self.entries.iter().position(|(entry, _, _)| entry == key)?;
+        Some(self.entries.swap_remove(index).1)
+    }
+
+    pub fn put(&mut self, key: K, texture: Texture) {
+        // Ex : a key which changes every frame (a timer)
+        while self.entries.len() >= self.size {
+            let oldest = self.entries
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, (_, _, last_used))| *last_used)
+                .map(|(index, _)| index);
+            match oldest {
+                Some(index) => destroy_texture(self.entries.swap_remove(index).1),
+                None => break,
+            }
+        }
+        self.entries.push((key, texture, self.frame));
+    }
+}
@@ -0,0 +1,957 @@
+use std::{collections::HashMap, fs, io::Read, path::{Path, PathBuf}};
+
+use base64::Engine;
+use serde::Deserialize;
+
+use crate::{gc2d::Gc2dResult, graphics::Graphics, image::{ImageHandle, Quad}};
+
+// Flags stored in the high bits of a Tiled gid
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+const GID_MASK: u32 = !(FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY);
+
+/*================================================================
+ *                          T I L E M A P
+ *================================================================*/
+/*
+ * Property : A custom property set in Tiled
+ *            (colors, files and classes are kept as strings)
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Property {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+pub type Properties = HashMap<String, Property>;
+
+/*
+ * TileFrame : A frame of an animated tile
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct TileFrame {
+    pub tile_id: u32,
+    // Duration in seconds
+    pub duration: f32,
+}
+
+pub struct Tileset {
+    pub name: String,
+    pub first_gid: u32,
+    pub image: ImageHandle,
+    pub tile_width: f32,
+    pub tile_height: f32,
+    pub columns: u32,
+    pub tile_count: u32,
+    pub margin: f32,
+    pub spacing: f32,
+    pub animations: HashMap<u32, Vec<TileFrame>>,
+    pub tile_properties: HashMap<u32, Properties>,
+}
+
+impl Tileset {
+    /*
+     * get_quad()
+     *
+     * @Brief : Quad of a tile (local id) in the tileset image
+     */
+    pub fn get_quad(&self, tile_id: u32) -> Quad {
+        let columns = self.columns.max(1);
+        Quad {
+            x: self.margin + (tile_id % columns) as f32 * (self.tile_width + self.spacing),
+            y: self.margin + (tile_id / columns) as f32 * (self.tile_height + self.spacing),
+            width: self.tile_width,
+            height: self.tile_height,
+        }
+    }
+
+    // Local id displayed at time (in seconds) for an animated tile
+    fn animated_tile(&self, tile_id: u32, time: f32) -> u32 {
+        if let Some(frames) = self.animations.get(&tile_id) {
+            let total: f32 = frames.iter().map(|frame| frame.duration).sum();
+            if total > 0. {
+                let mut t = time % total;
+                for frame in frames {
+                    if t < frame.duration {
+                        return frame.tile_id;
+                    }
+                    t -= frame.duration;
+                }
+            }
+        }
+        tile_id
+    }
+}
+
+pub struct TileLayer {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    // Visibility and offset include the groups of the layer
+    pub visible: bool,
+    pub offset_x: f32,
+    pub offset_y: f32,
+    pub properties: Properties,
+    // Gids row by row, 0 is an empty cell
+    pub data: Vec<u32>,
+}
+
+impl TileLayer {
+    /*
+     * get_gid()
+     *
+     * @Brief : Gid at the cell (column, row), without the flip flags
+     */
+    pub fn get_gid(&self, column: i32, row: i32) -> Option<u32> {
+        if column < 0 || row < 0 || column as u32 >= self.width || row as u32 >= self.height {
+            return None;
+        }
+        match self.data[(row as u32 * self.width + column as u32) as usize] & GID_MASK {
+            0 => None,
+            gid => Some(gid),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MapObject {
+    pub id: u32,
+    pub name: String,
+    pub class: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub rotation: f32,
+    pub visible: bool,
+    pub gid: Option<u32>,
+    pub properties: Properties,
+}
+
+// Positions of the objects include the offsets of the layer and its groups
+pub struct ObjectLayer {
+    pub name: String,
+    pub visible: bool,
+    pub properties: Properties,
+    pub objects: Vec<MapObject>,
+}
+
+pub enum Layer {
+    Tiles(TileLayer),
+    Objects(ObjectLayer),
+}
+
+impl Layer {
+    pub fn get_name(&self) -> &str {
+        match self {
+            Layer::Tiles(layer) => &layer.name,
+            Layer::Objects(layer) => &layer.name,
+        }
+    }
+}
+
+/*
+ * TileMap : An orthogonal map made with the Tiled editor
+ */
+pub struct TileMap {
+    pub width: u32,
+    pub height: u32,
+    pub tile_width: f32,
+    pub tile_height: f32,
+    pub properties: Properties,
+    pub tilesets: Vec<Tileset>,
+    pub layers: Vec<Layer>,
+    time: f32,
+}
+
+impl TileMap {
+    /*
+     * new_tilemap()
+     *
+     * @Brief : Load a Tiled map (.tmx or .json), the images of the
+     *          tilesets are loaded in the images manager
+     */
+    pub fn new_tilemap(graphics: &mut Graphics, filename: &str) -> Gc2dResult<Self> {
+        if filename.ends_with(".tmx") {
+            TileMap::from_tmx(graphics, filename)
+        } else {
+            TileMap::from_json(graphics, filename)
+        }
+    }
+
+    /*
+     * from_json()
+     */
+    pub fn from_json(graphics: &mut Graphics, filename: &str) -> Gc2dResult<Self> {
+        let map: json::Map = json::read(filename)?;
+
+        if map.orientation != "orthogonal" {
+            return Err(format!("{}: {} maps are not supported", filename, map.orientation));
+        }
+        if map.infinite {
+            return Err(format!("{}: infinite maps are not supported", filename));
+        }
+
+        check_tile_size(filename, map.tilewidth, map.tileheight)?;
+
+        let mut tilesets = Vec::new();
+        for tileset in map.tilesets {
+            tilesets.push(json::tileset(graphics, filename, tileset)?);
+        }
+
+        let mut layers = Vec::new();
+        json::layers(filename, map.layers, Group::ROOT, &mut layers)?;
+
+        Ok(TileMap::new(map.width, map.height, map.tilewidth, map.tileheight, json::properties(map.properties), tilesets, layers))
+    }
+
+    /*
+     * from_tmx()
+     */
+    pub fn from_tmx(graphics: &mut Graphics, filename: &str) -> Gc2dResult<Self> {
+        let text = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        let document = roxmltree::Document::parse(&text).map_err(|e| format!("{}: {}", filename, e))?;
+        let map = document.root_element();
+
+        let orientation = map.attribute("orientation").unwrap_or("orthogonal");
+        if orientation != "orthogonal" {
+            return Err(format!("{}: {} maps are not supported", filename, orientation));
+        }
+        if map.attribute("infinite") == Some("1") {
+            return Err(format!("{}: infinite maps are not supported", filename));
+        }
+
+        let tile_width = tmx::attribute(map, "tilewidth", 0.);
+        let tile_height = tmx::attribute(map, "tileheight", 0.);
+        check_tile_size(filename, tile_width, tile_height)?;
+
+        let mut tilesets = Vec::new();
+        for node in map.children().filter(|n| n.has_tag_name("tileset")) {
+            tilesets.push(tmx::tileset(graphics, filename, node)?);
+        }
+
+        let mut layers = Vec::new();
+        tmx::layers(filename, map, Group::ROOT, &mut layers)?;
+
+        Ok(TileMap::new(
+            tmx::attribute(map, "width", 0),
+            tmx::attribute(map, "height", 0),
+            tile_width,
+            tile_height,
+            tmx::properties(map),
+            tilesets,
+            layers,
+        ))
+    }
+
+    fn new(width: u32, height: u32, tile_width: f32, tile_height: f32, properties: Properties, mut tilesets: Vec<Tileset>, layers: Vec<Layer>) -> Self {
+        tilesets.sort_by_key(|tileset| tileset.first_gid);
+        Self {
+            width,
+            height,
+            tile_width,
+            tile_height,
+            properties,
+            tilesets,
+            layers,
+            time: 0.,
+        }
+    }
+
+    /*
+     * update()
+     *
+     * @Brief : Advance the animated tiles
+     */
+    pub fn update(&mut self, dt: f32) {
+        self.time += dt;
+    }
+
+    //=======================================================================
+    //                             LOOKUP
+    //=======================================================================
+    pub fn get_layer(&self, name: &str) -> Option<&Layer> {
+        self.layers.iter().find(|layer| layer.get_name() == name)
+    }
+
+    pub fn get_tile_layer(&self, name: &str) -> Option<&TileLayer> {
+        match self.get_layer(name) {
+            Some(Layer::Tiles(layer)) => Some(layer),
+            _ => None,
+        }
+    }
+
+    pub fn get_object_layer(&self, name: &str) -> Option<&ObjectLayer> {
+        match self.get_layer(name) {
+            Some(Layer::Objects(layer)) => Some(layer),
+            _ => None,
+        }
+    }
+
+    /*
+     * get_tileset()
+     *
+     * @Brief : Tileset of a gid, and the local id of the tile in it
+     */
+    pub fn get_tileset(&self, gid: u32) -> Option<(&Tileset, u32)> {
+        let gid = gid & GID_MASK;
+        self.tilesets
+            .iter()
+            .rev()
+            .find(|tileset| tileset.first_gid <= gid)
+            .filter(|tileset| gid - tileset.first_gid < tileset.tile_count)
+            .map(|tileset| (tileset, gid - tileset.first_gid))
+    }
+
+    /*
+     * world_to_tile()
+     *
+     * @Brief : Cell (column, row) at a world position
+     */
+    pub fn world_to_tile(&self, x: f32, y: f32) -> (i32, i32) {
+        ((x / self.tile_width).floor() as i32, (y / self.tile_height).floor() as i32)
+    }
+
+    /*
+     * get_tile_at()
+     *
+     * @Brief : Gid of the tile of a layer at a world position
+     */
+    pub fn get_tile_at(&self, layer: &str, x: f32, y: f32) -> Option<u32> {
+        let layer = self.get_tile_layer(layer)?;
+        let (column, row) = self.world_to_tile(x - layer.offset_x, y - layer.offset_y);
+        layer.get_gid(column, row)
+    }
+
+    /*
+     * get_tile_properties()
+     */
+    pub fn get_tile_properties(&self, gid: u32) -> Option<&Properties> {
+        let (tileset, tile_id) = self.get_tileset(gid)?;
+        tileset.tile_properties.get(&tile_id)
+    }
+
+    //=======================================================================
+    //                             DRAW
+    //=======================================================================
+    /*
+     * draw()
+     *
+     * @Brief : Draw the visible tile layers, view is the part of the world
+     *          shown at the top left corner of the screen
+     */
+    pub fn draw(&self, graphics: &mut Graphics, view: Quad) -> Gc2dResult<()> {
+        for layer in self.layers.iter() {
+            if let Layer::Tiles(layer) = layer {
+                if layer.visible {
+                    self.draw_tile_layer(graphics, layer, view)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /*
+     * draw_layer()
+     */
+    pub fn draw_layer(&self, graphics: &mut Graphics, name: &str, view: Quad) -> Gc2dResult<()> {
+        match self.get_tile_layer(name) {
+            Some(layer) => self.draw_tile_layer(graphics, layer, view),
+            None => Err(format!("Tile layer \"{}\" not found", name)),
+        }
+    }
+
+    fn draw_tile_layer(&self, graphics: &mut Graphics, layer: &TileLayer, view: Quad) -> Gc2dResult<()> {
+        let origin_x = view.x - layer.offset_x;
+        let origin_y = view.y - layer.offset_y;
+
+        // Tiles bigger than the cells are drawn up from the bottom of the cell : one more row
+        let (first_column, first_row) = self.world_to_tile(origin_x, origin_y);
+        let (last_column, last_row) = self.world_to_tile(origin_x + view.width, origin_y + view.height);
+
+        for row in first_row.max(0)..=last_row.saturating_add(1).min(layer.height as i32 - 1) {
+            for column in first_column.max(0)..=last_column.min(layer.width as i32 - 1) {
+
+                let raw = layer.data[(row as u32 * layer.width + column as u32) as usize];
+                if raw & GID_MASK == 0 {
+                    continue;
+                }
+
+                if let Some((tileset, tile_id)) = self.get_tileset(raw) {
+                    let tile_id = tileset.animated_tile(tile_id, self.time);
+                    let x = column as f32 * self.tile_width - origin_x;
+                    let y = (row + 1) as f32 * self.tile_height - tileset.tile_height - origin_y;
+
+                    // Diagonal flips (rotated tiles) are not drawn
+                    graphics.draw_region(
+                        tileset.image,
+                        tileset.get_quad(tile_id),
+                        Quad { x, y, width: tileset.tile_width, height: tileset.tile_height },
+                        raw & FLIPPED_HORIZONTALLY != 0,
+                        raw & FLIPPED_VERTICALLY != 0,
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// The cells of a map must have a size : positions are divided by it
+fn check_tile_size(filename: &str, tile_width: f32, tile_height: f32) -> Gc2dResult<()> {
+    if !tile_width.is_finite() || !tile_height.is_finite() || tile_width <= 0. || tile_height <= 0. {
+        return Err(format!("{}: invalid tile size {}x{}", filename, tile_width, tile_height));
+    }
+    Ok(())
+}
+
+// Number of cells of a layer, an error if it does not fit in memory
+fn cell_count(filename: &str, name: &str, width: u32, height: u32) -> Gc2dResult<usize> {
+    width
+        .checked_mul(height)
+        .and_then(|count| usize::try_from(count).ok())
+        .ok_or_else(|| format!("{}: layer \"{}\" is too large ({}x{})", filename, name, width, height))
+}
+
+// Visibility and offset of the groups around a layer, applied to the layer
+#[derive(Clone, Copy)]
+struct Group {
+    visible: bool,
+    offset_x: f32,
+    offset_y: f32,
+}
+
+impl Group {
+    const ROOT: Group = Group { visible: true, offset_x: 0., offset_y: 0. };
+
+    fn child(self, visible: bool, offset_x: f32, offset_y: f32) -> Group {
+        Group {
+            visible: self.visible && visible,
+            offset_x: self.offset_x + offset_x,
+            offset_y: self.offset_y + offset_y,
+        }
+    }
+}
+
+// Image path relative to the file which references it
+fn relative_path(filename: &str, path: &str) -> PathBuf {
+    Path::new(filename).parent().unwrap_or_else(|| Path::new("")).join(path)
+}
+
+// Decode the data of a tile layer ("csv" or "base64", optionally zlib / gzip compressed)
+fn decode_data(filename: &str, data: &str, encoding: &str, compression: &str) -> Gc2dResult<Vec<u32>> {
+    match encoding {
+        "csv" => data
+            .split(',')
+            .map(|gid| gid.trim())
+            .filter(|gid| !gid.is_empty())
+            .map(|gid| gid.parse::<u32>().map_err(|e| format!("{}: {}", filename, e)))
+            .collect(),
+        "base64" => {
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(data.trim())
+                .map_err(|e| format!("{}: {}", filename, e))?;
+
+            let mut raw = Vec::new();
+            let result = match compression {
+                "" => {
+                    raw = bytes;
+                    Ok(0)
+                },
+                "zlib" => flate2::read::ZlibDecoder::new(bytes.as_slice()).read_to_end(&mut raw),
+                "gzip" => flate2::read::GzDecoder::new(bytes.as_slice()).read_to_end(&mut raw),
+                other => return Err(format!("{}: {} compression is not supported", filename, other)),
+            };
+            result.map_err(|e| format!("{}: {}", filename, e))?;
+
+            Ok(raw
+                .chunks_exact(4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect())
+        },
+        other => Err(format!("{}: {} encoding is not supported", filename, other)),
+    }
+}
+
+//=======================================================================
+//                            JSON FORMAT
+//=======================================================================
+mod json {
+    use super::*;
+
+    pub(super) fn read<T: for<'de> Deserialize<'de>>(filename: &str) -> Gc2dResult<T> {
+        let text = fs::read_to_string(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        serde_json::from_str(&text).map_err(|e| format!("{}: {}", filename, e))
+    }
+
+    #[derive(Deserialize)]
+    pub(super) struct Map {
+        pub width: u32,
+        pub height: u32,
+        pub tilewidth: f32,
+        pub tileheight: f32,
+        #[serde(default)]
+        pub orientation: String,
+        #[serde(default)]
+        pub infinite: bool,
+        #[serde(default)]
+        pub properties: Vec<JsonProperty>,
+        #[serde(default)]
+        pub tilesets: Vec<JsonTileset>,
+        #[serde(default)]
+        pub layers: Vec<JsonLayer>,
+    }
+
+    #[derive(Deserialize)]
+    pub(super) struct JsonProperty {
+        name: String,
+        #[serde(rename = "type", default)]
+        kind: String,
+        value: serde_json::Value,
+    }
+
+    #[derive(Deserialize)]
+    pub(super) struct JsonTileset {
+        firstgid: Option<u32>,
+        source: Option<String>,
+        #[serde(default)]
+        name: String,
+        image: Option<String>,
+        #[serde(default)]
+        tilewidth: f32,
+        #[serde(default)]
+        tileheight: f32,
+        #[serde(default)]
+        columns: u32,
+        #[serde(default)]
+        tilecount: u32,
+        #[serde(default)]
+        margin: f32,
+        #[serde(default)]
+        spacing: f32,
+        #[serde(default)]
+        tiles: Vec<JsonTile>,
+    }
+
+    #[derive(Deserialize)]
+    struct JsonTile {
+        id: u32,
+        #[serde(default)]
+        animation: Vec<JsonFrame>,
+        #[serde(default)]
+        properties: Vec<JsonProperty>,
+    }
+
+    #[derive(Deserialize)]
+    struct JsonFrame {
+        tileid: u32,
+        duration: f32,
+    }
+
+    #[derive(Deserialize)]
+    pub(super) struct JsonLayer {
+        #[serde(rename = "type")]
+        kind: String,
+        #[serde(default)]
+        name: String,
+        #[serde(default)]
+        width: u32,
+        #[serde(default)]
+        height: u32,
+        #[serde(default = "visible")]
+        visible: bool,
+        #[serde(default)]
+        offsetx: f32,
+        #[serde(default)]
+        offsety: f32,
+        #[serde(default)]
+        properties: Vec<JsonProperty>,
+        data: Option<serde_json::Value>,
+        #[serde(default)]
+        encoding: String,
+        #[serde(default)]
+        compression: String,
+        #[serde(default)]
+        objects: Vec<JsonObject>,
+        #[serde(default)]
+        layers: Vec<JsonLayer>,
+    }
+
+    #[derive(Deserialize)]
+    struct JsonObject {
+        #[serde(default)]
+        id: u32,
+        #[serde(default)]
+        name: String,
+        // "type" before Tiled 1.9, "class" after
+        #[serde(default, alias = "class")]
+        r#type: String,
+        #[serde(default)]
+        x: f32,
+        #[serde(default)]
+        y: f32,
+        #[serde(default)]
+        width: f32,
+        #[serde(default)]
+        height: f32,
+        #[serde(default)]
+        rotation: f32,
+        #[serde(default = "visible")]
+        visible: bool,
+        gid: Option<u32>,
+        #[serde(default)]
+        properties: Vec<JsonProperty>,
+    }
+
+    fn visible() -> bool {
+        true
+    }
+
+    pub(super) fn properties(properties: Vec<JsonProperty>) -> Properties {
+        properties
+            .into_iter()
+            .map(|property| {
+                let value = match (property.kind.as_str(), &property.value) {
+                    ("bool", serde_json::Value::Bool(b)) => Property::Bool(*b),
+                    ("int", value) | ("object", value) => Property::Int(value.as_i64().unwrap_or(0)),
+                    ("float", value) => Property::Float(value.as_f64().unwrap_or(0.)),
+                    (_, serde_json::Value::String(s)) => Property::String(s.clone()),
+                    (_, value) => Property::String(value.to_string()),
+                };
+                (property.name, value)
+            })
+            .collect()
+    }
+
+    pub(super) fn tileset(graphics: &mut Graphics, filename: &str, tileset: JsonTileset) -> Gc2dResult<Tileset> {
+        let first_gid = tileset.firstgid.unwrap_or(1);
+
+        // External tileset
+        if let Some(source) = &tileset.source {
+            let path = relative_path(filename, source);
+            let path = path.to_string_lossy();
+            if path.ends_with(".tsx") {
+                return Err(format!("{}: TSX tilesets must be used from a TMX map", path));
+            }
+            let mut external: JsonTileset = read(&path)?;
+            external.firstgid = Some(first_gid);
+            external.source = None;
+            return self::tileset(graphics, &path, external);
+        }
+
+        let image = tileset
+            .image
+            .ok_or_else(|| format!("{}: tileset \"{}\" has no image (image collections are not supported)", filename, tileset.name))?;
+        let image = graphics.new_image(&relative_path(filename, &image).to_string_lossy())?;
+
+        let mut animations = HashMap::new();
+        let mut tile_properties = HashMap::new();
+        for tile in tileset.tiles {
+            if !tile.animation.is_empty() {
+                animations.insert(tile.id, tile.animation.iter().map(|frame| TileFrame {
+                    tile_id: frame.tileid,
+                    duration: frame.duration / 1000.,
+                }).collect());
+            }
+            if !tile.properties.is_empty() {
+                tile_properties.insert(tile.id, properties(tile.properties));
+            }
+        }
+
+        Ok(Tileset {
+            name: tileset.name,
+            first_gid,
+            image,
+            tile_width: tileset.tilewidth,
+            tile_height: tileset.tileheight,
+            columns: tileset.columns,
+            tile_count: tileset.tilecount,
+            margin: tileset.margin,
+            spacing: tileset.spacing,
+            animations,
+            tile_properties,
+        })
+    }
+
+    pub(super) fn layers(filename: &str, json_layers: Vec<JsonLayer>, group: Group, layers: &mut Vec<Layer>) -> Gc2dResult<()> {
+        for layer in json_layers {
+            let state = group.child(layer.visible, layer.offsetx, layer.offsety);
+
+            match layer.kind.as_str() {
+                "tilelayer" => {
+                    let data = match layer.data {
+                        Some(serde_json::Value::String(s)) => decode_data(filename, &s, &layer.encoding, &layer.compression)?,
+                        Some(value) => serde_json::from_value(value).map_err(|e| format!("{}: {}", filename, e))?,
+                        None => return Err(format!("{}: layer \"{}\" has no data", filename, layer.name)),
+                    };
+
+                    let count = cell_count(filename, &layer.name, layer.width, layer.height)?;
+                    if data.len() != count {
+                        return Err(format!("{}: layer \"{}\" has {} tiles instead of {}", filename, layer.name, data.len(), count));
+                    }
+
+                    layers.push(Layer::Tiles(TileLayer {
+                        name: layer.name,
+                        width: layer.width,
+                        height: layer.height,
+                        visible: state.visible,
+                        offset_x: state.offset_x,
+                        offset_y: state.offset_y,
+                        properties: properties(layer.properties),
+                        data,
+                    }));
+                },
+                "objectgroup" => {
+                    layers.push(Layer::Objects(ObjectLayer {
+                        name: layer.name,
+                        visible: state.visible,
+                        properties: properties(layer.properties),
+                        objects: layer.objects.into_iter().map(|object| MapObject {
+                            id: object.id,
+                            name: object.name,
+                            class: object.r#type,
+                            x: object.x + state.offset_x,
+                            y: object.y + state.offset_y,
+                            width: object.width,
+                            height: object.height,
+                            rotation: object.rotation,
+                            visible: object.visible,
+                            gid: object.gid,
+                            properties: properties(object.properties),
+                        }).collect(),
+                    }));
+                },
+                // Layers of a group are added in order
+                "group" => self::layers(filename, layer.layers, state, layers)?,
+                _ => {},
+            }
+        }
+        Ok(())
+    }
+}
+
+//=======================================================================
+//                             TMX FORMAT
+//=======================================================================
+mod tmx {
+    use std::str::FromStr;
+
+    use roxmltree::Node;
+
+    use super::*;
+
+    pub(super) fn attribute<T: FromStr>(node: Node, name: &str, default: T) -> T {
+        node.attribute(name)
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default)
+    }
+
+    pub(super) fn properties(node: Node) -> Properties {
+        let mut properties = Properties::new();
+        for list in node.children().filter(|n| n.has_tag_name("properties")) {
+            for property in list.children().filter(|n| n.has_tag_name("property")) {
+                let name = property.attribute("name").unwrap_or("").to_string();
+                // Multiline strings are stored in the text of the node
+                let value = property.attribute("value").or_else(|| property.text()).unwrap_or("");
+                let value = match property.attribute("type").unwrap_or("string") {
+                    "bool" => Property::Bool(value == "true"),
+                    "int" | "object" => Property::Int(value.parse().unwrap_or(0)),
+                    "float" => Property::Float(value.parse().unwrap_or(0.)),
+                    _ => Property::String(value.to_string()),
+                };
+                properties.insert(name, value);
+            }
+        }
+        properties
+    }
+
+    pub(super) fn tileset(graphics: &mut Graphics, filename: &str, node: Node) -> Gc2dResult<Tileset> {
+        let first_gid = attribute(node, "firstgid", 1);
+
+        // External tileset
+        if let Some(source) = node.attribute("source") {
+            let path = relative_path(filename, source);
+            let path = path.to_string_lossy();
+            let text = fs::read_to_string(path.as_ref()).map_err(|e| format!("{}: {}", path, e))?;
+            let document = roxmltree::Document::parse(&text).map_err(|e| format!("{}: {}", path, e))?;
+            let mut tileset = self::tileset(graphics, &path, document.root_element())?;
+            tileset.first_gid = first_gid;
+            return Ok(tileset);
+        }
+
+        let name = node.attribute("name").unwrap_or("").to_string();
+
+        let image = node
+            .children()
+            .find(|n| n.has_tag_name("image"))
+            .and_then(|n| n.attribute("source"))
+            .ok_or_else(|| format!("{}: tileset \"{}\" has no image (image collections are not supported)", filename, name))?;
+        let image = graphics.new_image(&relative_path(filename, image).to_string_lossy())?;
+
+        let mut animations = HashMap::new();
+        let mut tile_properties = HashMap::new();
+        for tile in node.children().filter(|n| n.has_tag_name("tile")) {
+            let id = attribute(tile, "id", 0);
+
+            if let Some(animation) = tile.children().find(|n| n.has_tag_name("animation")) {
+                animations.insert(id, animation
+                    .children()
+                    .filter(|n| n.has_tag_name("frame"))
+                    .map(|frame| TileFrame {
+                        tile_id: attribute(frame, "tileid", 0),
+                        duration: attribute(frame, "duration", 0.) / 1000.,
+                    })
+                    .collect());
+            }
+
+            let properties = properties(tile);
+            if !properties.is_empty() {
+                tile_properties.insert(id, properties);
+            }
+        }
+
+        Ok(Tileset {
+            name,
+            first_gid,
+            image,
+            tile_width: attribute(node, "tilewidth", 0.),
+            tile_height: attribute(node, "tileheight", 0.),
+            columns: attribute(node, "columns", 0),
+            tile_count: attribute(node, "tilecount", 0),
+            margin: attribute(node, "margin", 0.),
+            spacing: attribute(node, "spacing", 0.),
+            animations,
+            tile_properties,
+        })
+    }
+
+    pub(super) fn layers(filename: &str, parent: Node, group: Group, layers: &mut Vec<Layer>) -> Gc2dResult<()> {
+        for node in parent.children().filter(|n| n.is_element()) {
+            let name = node.attribute("name").unwrap_or("").to_string();
+            let state = group.child(
+                node.attribute("visible") != Some("0"),
+                attribute(node, "offsetx", 0.),
+                attribute(node, "offsety", 0.),
+            );
+
+            match node.tag_name().name() {
+                "layer" => {
+                    let width: u32 = attribute(node, "width", 0);
+                    let height: u32 = attribute(node, "height", 0);
+
+                    let data_node = node
+                        .children()
+                        .find(|n| n.has_tag_name("data"))
+                        .ok_or_else(|| format!("{}: layer \"{}\" has no data", filename, name))?;
+
+                    let data = match data_node.attribute("encoding") {
+                        Some(encoding) => decode_data(filename, data_node.text().unwrap_or(""), encoding, data_node.attribute("compression").unwrap_or(""))?,
+                        // Plain XML : one <tile gid=".."/> per cell
+                        None => data_node
+                            .children()
+                            .filter(|n| n.has_tag_name("tile"))
+                            .map(|tile| attribute(tile, "gid", 0))
+                            .collect(),
+                    };
+
+                    let count = cell_count(filename, &name, width, height)?;
+                    if data.len() != count {
+                        return Err(format!("{}: layer \"{}\" has {} tiles instead of {}", filename, name, data.len(), count));
+                    }
+
+                    layers.push(Layer::Tiles(TileLayer {
+                        name,
+                        width,
+                        height,
+                        visible: state.visible,
+                        offset_x: state.offset_x,
+                        offset_y: state.offset_y,
+                        properties: properties(node),
+                        data,
+                    }));
+                },
+                "objectgroup" => {
+                    layers.push(Layer::Objects(ObjectLayer {
+                        name,
+                        visible: state.visible,
+                        properties: properties(node),
+                        objects: node.children().filter(|n| n.has_tag_name("object")).map(|object| MapObject {
+                            id: attribute(object, "id", 0),
+                            name: object.attribute("name").unwrap_or("").to_string(),
+                            // "type" before Tiled 1.9, "class" after
+                            class: object.attribute("class").or_else(|| object.attribute("type")).unwrap_or("").to_string(),
+                            x: attribute::<f32>(object, "x", 0.) + state.offset_x,
+                            y: attribute::<f32>(object, "y", 0.) + state.offset_y,
+                            width: attribute(object, "width", 0.),
+                            height: attribute(object, "height", 0.),
+                            rotation: attribute(object, "rotation", 0.),
+                            visible: object.attribute("visible") != Some("0"),
+                            gid: object.attribute("gid").and_then(|gid| gid.parse().ok()),
+                            properties: properties(object),
+                        }).collect(),
+                    }));
+                },
+                // Layers of a group are added in order
+                "group" => self::layers(filename, node, state, layers)?,
+                _ => {},
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    const GIDS: [u32; 4] = [1, 0, 2147483650, 7];
+
+    fn encode(bytes: &[u8]) -> String {
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    }
+
+    fn raw() -> Vec<u8> {
+        GIDS.iter().flat_map(|gid| gid.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn decode_csv() {
+        let data = "1,0,\n2147483650, 7\n";
+        assert_eq!(decode_data("map.tmx", data, "csv", "").unwrap(), GIDS);
+        assert!(decode_data("map.tmx", "1,x", "csv", "").is_err());
+    }
+
+    #[test]
+    fn decode_base64() {
+        let data = format!("\n   {}\n", encode(&raw()));
+        assert_eq!(decode_data("map.tmx", &data, "base64", "").unwrap(), GIDS);
+    }
+
+    #[test]
+    fn decode_base64_zlib() {
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&raw()).unwrap();
+        let data = encode(&encoder.finish().unwrap());
+        assert_eq!(decode_data("map.tmx", &data, "base64", "zlib").unwrap(), GIDS);
+    }
+
+    #[test]
+    fn decode_base64_gzip() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&raw()).unwrap();
+        let data = encode(&encoder.finish().unwrap());
+        assert_eq!(decode_data("map.tmx", &data, "base64", "gzip").unwrap(), GIDS);
+    }
+
+    #[test]
+    fn decode_unsupported() {
+        assert!(decode_data("map.tmx", &encode(&raw()), "base64", "zstd").is_err());
+        assert!(decode_data("map.tmx", "", "xml", "").is_err());
+    }
+}
@@ -0,0 +1,168 @@
+use crate::color::Color;
+
+/*================================================================
+ *                      T R A N S I T I O N
+ *================================================================*/
+/*
+ * Easing : Speed curve of a transition over its duration
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Easing {
+    Linear,
+    // Slow start
+    EaseIn,
+    // Slow end
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    /*
+     * apply()
+     *
+     * @Brief : Eased value of t, from 0. to 1.
+     */
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0., 1.);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2. - t),
+            Easing::EaseInOut => {
+                if t < 0.5 { 2. * t * t } else { 1. - (-2. * t + 2.).powi(2) / 2. }
+            },
+        }
+    }
+}
+
+/*
+ * TransitionKind : How the screen changes
+ *
+ * Fade, wipes and iris cover the screen with a color until the midpoint,
+ * then uncover it. A crossfade blends the last frame before the transition
+ * into the new frames.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TransitionKind {
+    Fade(Color),
+    // Left to right
+    WipeHorizontal(Color),
+    // Top to bottom
+    WipeVertical(Color),
+    // Circle closing on the center of the screen, then opening
+    Iris(Color),
+    Crossfade,
+}
+
+type MidpointCallback = Box<dyn FnMut()>;
+
+/*
+ * Transition : Started with graphics.start_transition()
+ *
+ *   graphics.start_transition(
+ *       Transition::new(TransitionKind::Fade(Color::BLACK), 1.)
+ *           .with_easing(Easing::EaseInOut)
+ *           .on_midpoint(move || next_level.set(true)));
+ *
+ * The midpoint callback is where the scene changes : the screen is covered.
+ * A crossfade shows the old frame at once, so its callback is called on the
+ * first frame, right after the old frame is captured.
+ */
+pub struct Transition {
+    pub kind: TransitionKind,
+    pub duration: f32,
+    pub easing: Easing,
+    elapsed: f32,
+    midpoint: Option<MidpointCallback>,
+    midpoint_reached: bool,
+    // Last frame before a crossfade
+    pub(crate) capture: Option<sdl2::render::Texture>,
+}
+
+impl Transition {
+    pub fn new(kind: TransitionKind, duration: f32) -> Self {
+        Self {
+            kind,
+            duration: duration.max(0.),
+            easing: Easing::Linear,
+            elapsed: 0.,
+            midpoint: None,
+            midpoint_reached: false,
+            capture: None,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn on_midpoint<F: FnMut() + 'static>(mut self, callback: F) -> Self {
+        self.midpoint = Some(Box::new(callback));
+        self
+    }
+
+    /*
+     * get_progress()
+     *
+     * @Brief : From 0. (start) to 1. (over), without easing
+     */
+    pub fn get_progress(&self) -> f32 {
+        if self.duration > 0. {
+            (self.elapsed / self.duration).min(1.)
+        } else {
+            1.
+        }
+    }
+
+    pub fn is_midpoint_reached(&self) -> bool {
+        self.midpoint_reached
+    }
+
+    pub(crate) fn needs_capture(&self) -> bool {
+        self.kind == TransitionKind::Crossfade && self.capture.is_none()
+    }
+
+    /*
+     * update()
+     *
+     * @Brief : Advance of dt seconds, true when the transition is over
+     */
+    pub(crate) fn update(&mut self, dt: f32) -> bool {
+        // A crossfade starts once the old frame is captured
+        if self.needs_capture() {
+            return false;
+        }
+
+        if self.kind != TransitionKind::Crossfade {
+            self.elapsed += dt;
+        }
+
+        if !self.midpoint_reached && (self.kind == TransitionKind::Crossfade || self.get_progress() >= 0.5) {
+            self.midpoint_reached = true;
+            if let Some(callback) = self.midpoint.as_mut() {
+                callback();
+            }
+        }
+
+        if self.kind == TransitionKind::Crossfade {
+            self.elapsed += dt;
+        }
+
+        self.elapsed >= self.duration
+    }
+
+    /*
+     * coverage()
+     *
+     * @Brief : Part of the screen covered (0. to 1.), or for a crossfade the
+     *          opacity of the old frame
+     */
+    pub(crate) fn coverage(&self) -> f32 {
+        let progress = self.get_progress();
+        match self.kind {
+            TransitionKind::Crossfade => 1. - self.easing.apply(progress),
+            _ => self.easing.apply(1. - (2. * progress - 1.).abs()),
+        }
+    }
+}